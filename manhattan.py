@@ -1,50 +0,0 @@
-def manhattan(point_from, point_to):
-    return abs(point_from[0] - point_to[0]) + abs(point_from[1] - point_to[1])
-
-
-
-def print_table(target):
-    print("[")
-    for y in range(16):
-        print("[", end="")
-        for x in range(16):
-            result = ((manhattan((x, y), target) ) - 14.5 ) / 3
-            if(x == 15):
-                end = ""
-            else:
-                result_str = "{:.1f}".format(result) 
-                if(len(result_str) < 4):
-                    end = "  ,"
-                else:
-                    end = " ,"
-            print(result_str, end = end)
-        print("],")
-    print("]")
-
-print_table((0, 0))
-
-
-table = [
-    [-4.8, -4.5, -4.2, -3.8, -3.5, -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, -0.2, ],
-[-4.5, -4.2, -3.8, -3.5, -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.2, ],
-[-4.2, -3.8, -3.5, -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.5, ],
-[-3.8, -3.5, -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 0.8, ],
-[-3.5, -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.2, ],
-[-3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.5, ],
-[-2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 1.8, ],
-[-2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.2, ],
-[-2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.5, ],
-[-1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 2.8, ],
-[-1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.2, ],
-[-1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.6, 3.6, ],
-[-0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.6, 3.9, 3.9, ],
-[-0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.6, 3.9, 4.3, 4.3, ],
-[-0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.6, 3.9, 4.3, 4.6, 4.6, ],
-[0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.5, 3.9, 4.3, 4.6, 4.9, 4.9, ],
-]
-
-for (idx, row) in enumerate(table):
-    row.reverse()
-table.reverse()
-print(table)
-
@@ -0,0 +1,159 @@
+use crate::halma::{BoardState, DrawReason, GameState, Player};
+use crate::heuristics::{Heuristic, TableBuilder, WeightedHeuristic};
+
+use std::{error::Error, fmt::Display, str::FromStr};
+
+// how an unfinished game is scored once the round limit runs out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AdjudicationMethod {
+    // pieces already standing in the target camp
+    TargetPieces,
+    // steps the pieces still have to make to reach the target camp
+    Distance,
+    // the plain proximity table heuristic, the same for every player
+    Heuristic,
+}
+
+// the player with the best score wins, unless they are ahead of the next one by less than `margin`
+#[derive(Clone, Debug)]
+pub struct Adjudication {
+    pub method: AdjudicationMethod,
+    pub margin: f32,
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        Adjudication {
+            method: AdjudicationMethod::Distance,
+            margin: 0.,
+        }
+    }
+}
+
+impl Adjudication {
+    // higher is better for the player
+    pub fn score(&self, board_state: &BoardState, player: Player) -> f32 {
+        match self.method {
+            AdjudicationMethod::TargetPieces => {
+                let target = board_state.config.target(player);
+                board_state
+                    .coords(player)
+                    .iter()
+                    .filter(|coords| target.contains(coords))
+                    .count() as f32
+            }
+            AdjudicationMethod::Distance => -(board_state.distance_to_target(player) as f32),
+            AdjudicationMethod::Heuristic => {
+                let mut reference =
+                    WeightedHeuristic::proximity(&board_state.config, &TableBuilder::default(), 1.);
+                reference.evaluate(board_state, player, 0)
+            }
+        }
+    }
+
+    // `Won` for the leading player or `Draw` when nobody leads by the margin
+    pub fn adjudicate(&self, board_state: &BoardState) -> GameState {
+        let mut scores: Vec<(Player, f32)> = board_state
+            .config
+            .players
+            .iter()
+            .map(|player| (*player, self.score(board_state, *player)))
+            .collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let lead = scores[0].1 - scores[1].1;
+        if lead <= 0. || lead < self.margin {
+            GameState::Draw(DrawReason::Adjudication)
+        } else {
+            GameState::Won(scores[0].0)
+        }
+    }
+}
+
+impl Display for AdjudicationMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdjudicationMethod::TargetPieces => write!(f, "target"),
+            AdjudicationMethod::Distance => write!(f, "distance"),
+            AdjudicationMethod::Heuristic => write!(f, "heuristic"),
+        }
+    }
+}
+
+// `<target|distance|heuristic>[:margin]`, e.g. `distance:4`
+impl FromStr for Adjudication {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (method, margin) = s.split_once(':').unwrap_or((s, "0"));
+        let method = match method {
+            "target" => AdjudicationMethod::TargetPieces,
+            "distance" => AdjudicationMethod::Distance,
+            "heuristic" => AdjudicationMethod::Heuristic,
+            _ => return Err(format!("Unknown adjudication method: {}", method).into()),
+        };
+        Ok(Adjudication {
+            method,
+            margin: margin.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::halma::{BoardConfig, Coords};
+    use std::rc::Rc;
+
+    fn start() -> BoardState {
+        BoardState::start_position(Rc::new(BoardConfig::named("8x8-10").unwrap()))
+    }
+
+    #[test]
+    fn methods_parse() {
+        let adjudication: Adjudication = "distance:4".parse().unwrap();
+        assert_eq!(adjudication.method, AdjudicationMethod::Distance);
+        assert_eq!(adjudication.margin, 4.);
+        let adjudication: Adjudication = "target".parse().unwrap();
+        assert_eq!(adjudication.method, AdjudicationMethod::TargetPieces);
+        assert_eq!(adjudication.margin, 0.);
+        for bad in ["closest", "distance:x", ""] {
+            assert!(bad.parse::<Adjudication>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn the_leader_wins_by_the_margin() {
+        let board_state = start();
+        for method in ["target", "distance", "heuristic"] {
+            let adjudication: Adjudication = method.parse().unwrap();
+            assert_eq!(
+                adjudication.adjudicate(&board_state),
+                GameState::Draw(DrawReason::Adjudication),
+                "{}",
+                method
+            );
+        }
+
+        // black has one piece in the middle of the board and one in the target camp
+        let mut board_state = start();
+        let target_square = Coords { x: 4, y: 7 };
+        let white = board_state.coords_mut(Player::White);
+        let blocker = white.iter().position(|coords| *coords == target_square);
+        white[blocker.unwrap()] = Coords { x: 4, y: 4 };
+        board_state.coords_mut(Player::Black)[0] = target_square;
+        for method in ["target", "distance", "heuristic"] {
+            let adjudication: Adjudication = method.parse().unwrap();
+            assert_eq!(
+                adjudication.adjudicate(&board_state),
+                GameState::Won(Player::Black),
+                "{}",
+                method
+            );
+        }
+        let adjudication: Adjudication = "target:2".parse().unwrap();
+        assert_eq!(
+            adjudication.adjudicate(&board_state),
+            GameState::Draw(DrawReason::Adjudication)
+        );
+    }
+}
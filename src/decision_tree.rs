@@ -1,7 +1,5 @@
-use crate::halma::{
-    board_state_to_string, BoardState, Coords, GameState, Player, DIRECTIONS, PLAYER_BLACK_BASE,
-    PLAYER_WHITE_BASE,
-};
+use crate::halma::{Blocked, BoardState, Coords, DrawReason, GameState, Move, Player, Position};
+use crate::render::{render_board, use_color};
 
 use petgraph::{prelude::*, Graph};
 
@@ -9,7 +7,7 @@ use std::fmt::Display;
 
 #[derive(Clone, Debug)]
 pub struct DecisionTreeNode {
-    pub board_state: BoardState,
+    pub position: Position,
     pub game_state: GameState,
     pub children: Vec<DecisionTreeNode>,
     pub generated: bool,
@@ -19,76 +17,95 @@ impl Display for DecisionTreeNode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Node:\nGame state: {:?}, children amount: {}, generated: {}\n{}",
+            "Node:\nGame state: {:?}, to move: {:?}, ply: {}, children amount: {}, generated: {}\n{}",
             &self.game_state,
+            &self.position.to_move,
+            &self.position.ply,
             &self.children.len(),
             &self.generated,
-            board_state_to_string(&self.board_state)
+            render_board(
+                &self.position.board_state,
+                self.position.last_move.as_ref(),
+                use_color()
+            )
         )
     }
 }
 
 impl DecisionTreeNode {
-    pub fn new(board_state: BoardState, game_state: GameState) -> Self {
+    pub fn new(position: Position) -> Self {
+        let game_state = if position.ply > 0 && position.board_state.is_won(position.moved()) {
+            GameState::Won(position.moved())
+        } else {
+            GameState::InProgress
+        };
         DecisionTreeNode {
-            board_state,
+            position,
             game_state,
             children: Vec::with_capacity(40),
             generated: false,
         }
     }
-    // for a given state of the board, generate all possible moves and set them as children
-    pub fn generate_children(&mut self, player_moving: Player) {
+    // for a given state of the board, generate all possible moves of the side to move
+    // and set them as children
+    pub fn generate_children(&mut self) {
         self.generated = true;
-        let my_coords = match player_moving {
-            Player::Black => self.board_state.black_coords,
-            Player::White => self.board_state.white_coords,
-        };
+        let config = self.position.board_state.config.clone();
+        let player_moving = self.position.to_move;
+        let my_coords = self.position.board_state.coords(player_moving).clone();
         // for y in 0..16 {
         //     for x in 0..16 {
         for (from_coords_idx, from_coords) in my_coords.into_iter().enumerate() {
-            let my_winning_coords = match player_moving {
-                Player::Black => PLAYER_WHITE_BASE,
-                Player::White => PLAYER_BLACK_BASE,
-            };
-            //check all 8 possible dirctions
-            for direction in DIRECTIONS.into_iter() {
+            //check all possible directions of the board
+            for direction in config.directions().iter().copied() {
                 let move_to = Coords {
                     x: from_coords.x + direction.x,
                     y: from_coords.y + direction.y,
                 };
 
-                if move_to.is_in_board() {
+                if config.is_in_board(move_to) {
                     //normal moves
                     if self.is_tile_empty(move_to) {
-                        if my_winning_coords.contains(&from_coords) {
-                            if my_winning_coords.contains(&move_to) {
-                                self.add_child_node(from_coords_idx, move_to, player_moving);
-                            }
-                        } else {
-                            self.add_child_node(from_coords_idx, move_to, player_moving);
+                        if config.allows_move(player_moving, from_coords, move_to) {
+                            let step = Move {
+                                from: from_coords,
+                                to: move_to,
+                                path: vec![move_to],
+                            };
+                            self.add_child_node(from_coords_idx, step, player_moving);
                         }
                     //jumping
-                    } else {
+                    } else if self
+                        .position
+                        .board_state
+                        .can_jump_over(move_to, player_moving)
+                    {
                         let jump_to = Coords {
                             x: move_to.x + direction.x,
                             y: move_to.y + direction.y,
                         };
-                        if jump_to.is_in_board() {
+                        if config.is_in_board(jump_to) {
                             if self.is_tile_empty(jump_to) {
-                                let mut jump_points: Vec<Coords> = vec![jump_to];
+                                // every jump point remembers the index of the point it was reached from
+                                let mut jump_points: Vec<(Coords, Option<usize>)> =
+                                    vec![(jump_to, None)];
                                 self.generate_valid_jumps_for_point(
                                     &mut jump_points,
-                                    jump_to,
+                                    0,
                                     direction,
+                                    player_moving,
                                 );
-                                for jump_point in jump_points {
-                                    if my_winning_coords.contains(&from_coords) {
-                                        if !my_winning_coords.contains(&jump_point) {
-                                            continue;
-                                        }
+                                for jump_point_idx in 0..jump_points.len() {
+                                    let jump_point = jump_points[jump_point_idx].0;
+                                    if !config.allows_move(player_moving, from_coords, jump_point) {
+                                        continue;
                                     }
-                                    self.add_child_node(from_coords_idx, jump_point, player_moving);
+                                    let jump = Move {
+                                        from: from_coords,
+                                        to: jump_point,
+                                        path: Self::jump_path(&jump_points, jump_point_idx),
+                                    };
+                                    self.add_child_node(from_coords_idx, jump, player_moving);
                                 }
                             }
                         }
@@ -96,46 +113,98 @@ impl DecisionTreeNode {
                 }
             }
         }
+        // only moves taking a piece out of the home camp count while the player has to vacate it,
+        // unless there are none
+        let moves_made = self.position.ply / config.players.len() as u32;
+        if self
+            .position
+            .board_state
+            .must_vacate_home(player_moving, moves_made)
+        {
+            let home = config.base(player_moving);
+            let leaves_home = |child: &DecisionTreeNode| {
+                child.position.last_move.as_ref().is_some_and(|last_move| {
+                    home.contains(&last_move.from) && !home.contains(&last_move.to)
+                })
+            };
+            if self.children.iter().any(leaves_home) {
+                self.children.retain(leaves_home);
+            }
+        }
+        // a blocked player either passes or the rules end the game right here
+        if self.children.is_empty() {
+            match config.rules.blocked {
+                Blocked::Pass => {
+                    let child = Self::new(self.position.play(None));
+                    self.children.push(child);
+                }
+                Blocked::Loss => {
+                    self.game_state = GameState::Won(config.next_player(player_moving))
+                }
+                Blocked::Draw => self.game_state = GameState::Draw(DrawReason::Blocked),
+            }
+        }
     }
 
-    fn add_child_node(&mut self, move_from_idx: usize, move_to: Coords, player_moved: Player) {
-        let mut new_board_state = self.board_state.clone();
-        Self::move_tile(&mut new_board_state, move_from_idx, move_to, player_moved);
-        let new_game_state = if Self::is_game_won(&new_board_state, player_moved) {
-            GameState::Won(player_moved)
-        } else {
-            GameState::Moved(player_moved)
+    fn add_child_node(&mut self, move_from_idx: usize, player_move: Move, player_moved: Player) {
+        let mut new_board_state = self.position.board_state.clone();
+        Self::move_tile(
+            &mut new_board_state,
+            move_from_idx,
+            player_move.to,
+            player_moved,
+        );
+        let new_position = Position {
+            to_move: new_board_state.config.next_player(player_moved),
+            board_state: new_board_state,
+            ply: self.position.ply + 1,
+            last_move: Some(player_move),
         };
-        self.children
-            .push(Self::new(new_board_state, new_game_state))
+        self.children.push(Self::new(new_position))
+    }
+
+    // walk the parent links back to the first jump to get the full jump path
+    fn jump_path(jump_points: &[(Coords, Option<usize>)], last_idx: usize) -> Vec<Coords> {
+        let mut path = vec![jump_points[last_idx].0];
+        let mut parent = jump_points[last_idx].1;
+        while let Some(parent_idx) = parent {
+            path.push(jump_points[parent_idx].0);
+            parent = jump_points[parent_idx].1;
+        }
+        path.reverse();
+        path
     }
 
     fn generate_valid_jumps_for_point(
         &self,
-        jump_points: &mut Vec<Coords>,
-        point: Coords,
+        jump_points: &mut Vec<(Coords, Option<usize>)>,
+        point_idx: usize,
         previous_direction: Coords,
+        player_moving: Player,
     ) {
-        for direction in DIRECTIONS {
+        let point = jump_points[point_idx].0;
+        let board_state = &self.position.board_state;
+        for direction in board_state.config.directions().iter().copied() {
             if direction != previous_direction {
                 let check_point = Coords {
                     x: point.x + direction.x,
                     y: point.y + direction.y,
                 };
-                if check_point.is_in_board() {
-                    if !self.is_tile_empty(check_point) {
+                if board_state.config.is_in_board(check_point) {
+                    if board_state.can_jump_over(check_point, player_moving) {
                         let jump_to_point = Coords {
                             x: check_point.x + direction.x,
                             y: check_point.y + direction.y,
                         };
-                        if jump_to_point.is_in_board() {
+                        if board_state.config.is_in_board(jump_to_point) {
                             if self.is_tile_empty(jump_to_point) {
-                                if !jump_points.contains(&jump_to_point) {
-                                    jump_points.push(jump_to_point);
+                                if !jump_points.iter().any(|(p, _)| *p == jump_to_point) {
+                                    jump_points.push((jump_to_point, Some(point_idx)));
                                     self.generate_valid_jumps_for_point(
                                         jump_points,
-                                        jump_to_point,
+                                        jump_points.len() - 1,
                                         direction,
+                                        player_moving,
                                     );
                                 }
                             }
@@ -148,48 +217,20 @@ impl DecisionTreeNode {
 
     #[inline]
     fn is_tile_empty(&self, coords: Coords) -> bool {
-        (!self.board_state.black_coords.contains(&coords))
-            && (!self.board_state.white_coords.contains(&coords))
+        self.position.board_state.is_empty(coords)
     }
 
     #[inline]
     fn move_tile(board_state: &mut BoardState, from_idx: usize, to: Coords, player: Player) {
-        match player {
-            Player::Black => {
-                board_state.black_coords[from_idx] = to;
-            }
-            Player::White => {
-                board_state.white_coords[from_idx] = to;
-            }
-        };
-    }
-
-    #[inline]
-    fn is_game_won(board_state: &BoardState, player_moved: Player) -> bool {
-        let winning_positions = match player_moved {
-            Player::Black => PLAYER_WHITE_BASE,
-            Player::White => PLAYER_BLACK_BASE,
-        };
-        let my_positions = match player_moved {
-            Player::Black => &board_state.black_coords,
-            Player::White => &board_state.white_coords,
-        };
-
-        for my_position in my_positions.iter() {
-            if !winning_positions.contains(&my_position) {
-                return false;
-            }
-        }
-        return true;
+        board_state.coords_mut(player)[from_idx] = to;
     }
 }
 
 // generate all the moves for the game
-// first player is always black
-// get the first state of the board, generate a decision tree node for it, then for all its children and so on, until either player wins
+// get the first position, generate a decision tree node for it, then for all its children and so on, until either player wins
 pub fn generate_tree(
     max_depth: Option<u64>,
-    first_board_state: BoardState,
+    first_position: Position,
     graph: &mut Option<&mut Graph<DecisionTreeNode, u64>>,
 ) -> DecisionTreeNode {
     fn generate_tree_inner(
@@ -199,17 +240,15 @@ pub fn generate_tree(
         graph: &mut Option<&mut Graph<DecisionTreeNode, u64>>,
         parent_index: Option<NodeIndex>,
     ) {
-        let player = match node.game_state {
-            GameState::Start(player) => player,
-            GameState::Moved(player) => player,
-            GameState::Won(player) => player,
-        };
+        if node.game_state != GameState::InProgress {
+            return;
+        }
         if max_depth.is_some() {
             if current_depth > max_depth.unwrap() {
                 return;
             }
         }
-        node.generate_children(player.other());
+        node.generate_children();
         for mut child in &mut node.children {
             let mut child_index: Option<NodeIndex> = None;
             if graph.is_some() {
@@ -221,7 +260,7 @@ pub fn generate_tree(
                 );
             }
 
-            if !matches!(child.game_state, GameState::Won(_)) {
+            if child.game_state == GameState::InProgress {
                 generate_tree_inner(&mut child, current_depth + 1, max_depth, graph, child_index);
             } else {
                 //println!("Child won!");
@@ -230,12 +269,7 @@ pub fn generate_tree(
         }
         return;
     }
-    let mut first_node = DecisionTreeNode {
-        board_state: first_board_state,
-        game_state: GameState::Start(Player::White),
-        children: Vec::with_capacity(40),
-        generated: false,
-    };
+    let mut first_node = DecisionTreeNode::new(first_position);
     let mut first_index: Option<NodeIndex> = None;
     if graph.is_some() {
         first_index = Some(graph.as_mut().unwrap().add_node(first_node.clone()));
@@ -0,0 +1,712 @@
+use crate::halma::{BoardConfig, BoardState, Coords, JumpOver, Player};
+use crate::heuristics::ProximityTables;
+use rand::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+// a single measurable property of a position, combined with others by `WeightedHeuristic`;
+// higher values are better for `player` before weighting, except where noted
+pub trait Feature: FeatureClone {
+    fn value(&mut self, board_state: &BoardState, player: Player, tables: &ProximityTables) -> f32;
+    fn name(&self) -> String;
+    // only features drawing random numbers use the seed
+    fn seed(&mut self, _seed: u64) {}
+}
+
+// lets heuristics holding boxed features be cloned
+pub trait FeatureClone {
+    fn clone_box(&self) -> Box<dyn Feature>;
+}
+
+impl<T: 'static + Feature + Clone> FeatureClone for T {
+    fn clone_box(&self) -> Box<dyn Feature> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Feature> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+// sum of the proximity table values of all pieces
+#[derive(Clone)]
+pub struct Progress;
+
+// proximity table value of the piece furthest ahead
+#[derive(Clone)]
+pub struct LeadingPiece;
+
+// proximity table value of the piece furthest behind
+#[derive(Clone)]
+pub struct Straggler;
+
+// pieces still in the home camp, usually weighted negatively
+#[derive(Clone)]
+pub struct HomeOccupancy;
+
+// pieces already in the target camp
+#[derive(Clone)]
+pub struct TargetOccupancy;
+
+// -0.1 for every piece on the top or bottom row, as the original heuristics count it
+#[derive(Clone)]
+pub struct Edges;
+
+// -0.1 for every piece on any of the four edges of the board, which unlike `Edges` looks
+// the same from every side
+#[derive(Clone)]
+pub struct Border;
+
+// -1 for every opponent piece in its home camp next to one of the player's pieces
+#[derive(Clone)]
+pub struct Blocking;
+
+// steps and single jumps the player's pieces can make
+#[derive(Clone)]
+pub struct Mobility;
+
+// minus the moves a piece of the player's army needs on average to reach the target camp, which
+// keeps the value between about -11 and 0 on every board: for every piece a breadth first search
+// over steps and whole jump chains with the other pieces standing still; positions already seen
+// are looked up instead of searched again
+#[derive(Clone, Default)]
+pub struct MovesLeft {
+    cache: HashMap<u64, f32>,
+}
+
+// minus the steps the pieces still outside the target camp lose by having to fill its empty
+// squares, every piece going to its own square, instead of each stopping at the nearest square
+// of the camp the way `distance_to_target` counts them; 0 until most pieces have arrived, as
+// before that they hardly compete for squares and the tables guide them well enough
+#[derive(Clone)]
+pub struct Assignment;
+
+// positions remembered by `MovesLeft` before it starts over
+const MOVES_LEFT_CACHE_SIZE: usize = 200_000;
+
+// uniformly random value between -100 and 100
+#[derive(Clone)]
+pub struct Noise {
+    pub rng: StdRng,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Noise {
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl Feature for Progress {
+    fn value(&mut self, board_state: &BoardState, player: Player, tables: &ProximityTables) -> f32 {
+        board_state
+            .coords(player)
+            .iter()
+            .map(|coords| tables.value(player, *coords))
+            .sum()
+    }
+
+    fn name(&self) -> String {
+        "progress".to_owned()
+    }
+}
+
+impl Feature for LeadingPiece {
+    fn value(&mut self, board_state: &BoardState, player: Player, tables: &ProximityTables) -> f32 {
+        board_state
+            .coords(player)
+            .iter()
+            .map(|coords| tables.value(player, *coords))
+            .fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    fn name(&self) -> String {
+        "leading".to_owned()
+    }
+}
+
+impl Feature for Straggler {
+    fn value(&mut self, board_state: &BoardState, player: Player, tables: &ProximityTables) -> f32 {
+        board_state
+            .coords(player)
+            .iter()
+            .map(|coords| tables.value(player, *coords))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    fn name(&self) -> String {
+        "straggler".to_owned()
+    }
+}
+
+impl Feature for HomeOccupancy {
+    fn value(&mut self, board_state: &BoardState, player: Player, _: &ProximityTables) -> f32 {
+        let home = board_state.config.base(player);
+        board_state
+            .coords(player)
+            .iter()
+            .filter(|coords| home.contains(coords))
+            .count() as f32
+    }
+
+    fn name(&self) -> String {
+        "home".to_owned()
+    }
+}
+
+impl Feature for TargetOccupancy {
+    fn value(&mut self, board_state: &BoardState, player: Player, _: &ProximityTables) -> f32 {
+        let target = board_state.config.target(player);
+        board_state
+            .coords(player)
+            .iter()
+            .filter(|coords| target.contains(coords))
+            .count() as f32
+    }
+
+    fn name(&self) -> String {
+        "target".to_owned()
+    }
+}
+
+impl Feature for Edges {
+    fn value(&mut self, board_state: &BoardState, player: Player, _: &ProximityTables) -> f32 {
+        board_state
+            .coords(player)
+            .iter()
+            .map(|coords| discourage_edges(coords, &board_state.config))
+            .sum()
+    }
+
+    fn name(&self) -> String {
+        "edges".to_owned()
+    }
+}
+
+impl Feature for Border {
+    fn value(&mut self, board_state: &BoardState, player: Player, _: &ProximityTables) -> f32 {
+        let config = &board_state.config;
+        let on_border = |coords: &&Coords| {
+            coords.x == 0
+                || coords.y == 0
+                || coords.x == config.width - 1
+                || coords.y == config.height - 1
+        };
+        -0.1 * board_state.coords(player).iter().filter(on_border).count() as f32
+    }
+
+    fn name(&self) -> String {
+        "border".to_owned()
+    }
+}
+
+impl Feature for Blocking {
+    fn value(&mut self, board_state: &BoardState, player: Player, _: &ProximityTables) -> f32 {
+        board_state
+            .coords(player)
+            .iter()
+            .map(|coords| dont_block_other_players_in_base(coords, board_state, player))
+            .sum()
+    }
+
+    fn name(&self) -> String {
+        "blocking".to_owned()
+    }
+}
+
+impl Feature for Mobility {
+    fn value(&mut self, board_state: &BoardState, player: Player, _: &ProximityTables) -> f32 {
+        let config = &board_state.config;
+        let mut moves = 0;
+        for coords in board_state.coords(player) {
+            for direction in config.directions() {
+                let next = Coords {
+                    x: coords.x + direction.x,
+                    y: coords.y + direction.y,
+                };
+                if !config.is_in_board(next) {
+                    continue;
+                }
+                if board_state.is_empty(next) {
+                    moves += 1;
+                } else if board_state.can_jump_over(next, player) {
+                    let jump_to = Coords {
+                        x: next.x + direction.x,
+                        y: next.y + direction.y,
+                    };
+                    if config.is_in_board(jump_to) && board_state.is_empty(jump_to) {
+                        moves += 1;
+                    }
+                }
+            }
+        }
+        moves as f32
+    }
+
+    fn name(&self) -> String {
+        "mobility".to_owned()
+    }
+}
+
+impl Feature for MovesLeft {
+    fn value(&mut self, board_state: &BoardState, player: Player, _: &ProximityTables) -> f32 {
+        let hash = board_state.position_hash(player);
+        if let Some(value) = self.cache.get(&hash) {
+            return *value;
+        }
+        if self.cache.len() >= MOVES_LEFT_CACHE_SIZE {
+            self.cache.clear();
+        }
+        let config = &board_state.config;
+        let mut occupied = vec![vec![None; config.width as usize]; config.height as usize];
+        for other_player in &config.players {
+            for coords in board_state.coords(*other_player) {
+                occupied[coords.y as usize][coords.x as usize] = Some(*other_player);
+            }
+        }
+        let target = config.target(player);
+        let pieces = board_state.coords(player);
+        let moves: u32 = pieces
+            .iter()
+            .map(|coords| {
+                if target.contains(coords) {
+                    return 0;
+                }
+                occupied[coords.y as usize][coords.x as usize] = None;
+                let moves = moves_to_target(config, &occupied, player, *coords).unwrap_or_else(
+                    // walled off for now, counted as if every move were a step
+                    || {
+                        target
+                            .iter()
+                            .map(|square| config.distance(*coords, *square) as u32)
+                            .min()
+                            .unwrap_or(0)
+                    },
+                );
+                occupied[coords.y as usize][coords.x as usize] = Some(player);
+                moves
+            })
+            .sum();
+        let value = -(moves as f32) / pieces.len().max(1) as f32;
+        self.cache.insert(hash, value);
+        value
+    }
+
+    fn name(&self) -> String {
+        "moves".to_owned()
+    }
+}
+
+// least number of moves taking the piece on `from` into the target camp, everything in `occupied`
+// staying where it is except for the opponents' pieces still in the camp
+fn moves_to_target(
+    config: &BoardConfig,
+    occupied: &[Vec<Option<Player>>],
+    player: Player,
+    from: Coords,
+) -> Option<u32> {
+    let target = config.target(player);
+    let is_free = |coords: Coords| {
+        config.is_in_board(coords) && occupied[coords.y as usize][coords.x as usize].is_none()
+    };
+    let can_jump_over = |coords: Coords| {
+        config.is_in_board(coords)
+            && match (
+                config.rules.jump_over,
+                occupied[coords.y as usize][coords.x as usize],
+            ) {
+                (_, None) => false,
+                (JumpOver::AnyPiece, Some(_)) => true,
+                (JumpOver::OwnPieces, Some(other_player)) => other_player == player,
+            }
+    };
+    let mut moves = vec![vec![None; config.width as usize]; config.height as usize];
+    moves[from.y as usize][from.x as usize] = Some(0);
+    let mut queue = VecDeque::from([from]);
+    while let Some(square) = queue.pop_front() {
+        let moves_here = moves[square.y as usize][square.x as usize].unwrap();
+        if target.contains(&square) {
+            return Some(moves_here);
+        }
+        // the squares one step away, then every landing square of a chain of jumps
+        let mut reached = Vec::new();
+        for direction in config.directions() {
+            let next = Coords {
+                x: square.x + direction.x,
+                y: square.y + direction.y,
+            };
+            // opponents still sitting in the target camp are expected to leave it
+            let vacating = config.is_in_board(next)
+                && target.contains(&next)
+                && occupied[next.y as usize][next.x as usize] != Some(player);
+            if is_free(next) || vacating {
+                reached.push(next);
+            }
+        }
+        let mut jumps = vec![square];
+        let mut jump_idx = 0;
+        while jump_idx < jumps.len() {
+            let jump_from = jumps[jump_idx];
+            jump_idx += 1;
+            for direction in config.directions() {
+                let over = Coords {
+                    x: jump_from.x + direction.x,
+                    y: jump_from.y + direction.y,
+                };
+                let jump_to = Coords {
+                    x: over.x + direction.x,
+                    y: over.y + direction.y,
+                };
+                if can_jump_over(over) && is_free(jump_to) && !jumps.contains(&jump_to) {
+                    jumps.push(jump_to);
+                }
+            }
+        }
+        reached.extend(jumps.into_iter().skip(1));
+        for next in reached {
+            if moves[next.y as usize][next.x as usize].is_none()
+                && config.allows_move(player, square, next)
+            {
+                moves[next.y as usize][next.x as usize] = Some(moves_here + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+impl Feature for Assignment {
+    fn value(&mut self, board_state: &BoardState, player: Player, _: &ProximityTables) -> f32 {
+        let config = &board_state.config;
+        let target = config.target(player);
+        let pieces = board_state.coords(player);
+        let outside: Vec<Coords> = pieces
+            .iter()
+            .filter(|coords| !target.contains(coords))
+            .copied()
+            .collect();
+        let empty: Vec<Coords> = target
+            .iter()
+            .filter(|coords| board_state.is_empty(**coords))
+            .copied()
+            .collect();
+        if outside.is_empty() || outside.len() * 2 > pieces.len() || empty.is_empty() {
+            return 0.;
+        }
+        let costs: Vec<Vec<u32>> = outside
+            .iter()
+            .map(|piece| {
+                empty
+                    .iter()
+                    .map(|square| config.distance(*piece, *square) as u32)
+                    .collect()
+            })
+            .collect();
+        let steps = if outside.len() <= empty.len() {
+            min_cost_assignment(&costs)
+        } else {
+            // more pieces than free squares, which other players must be sitting on: the squares
+            // get their pieces and the pieces left over still count to their nearest square
+            let by_square: Vec<Vec<u32>> = (0..empty.len())
+                .map(|square| costs.iter().map(|row| row[square]).collect())
+                .collect();
+            let assigned = min_cost_assignment_rows(&by_square);
+            let left_over: u32 = (0..outside.len())
+                .filter(|piece| !assigned.contains(&Some(*piece)))
+                .map(|piece| costs[piece].iter().copied().min().unwrap())
+                .sum();
+            assigned
+                .iter()
+                .enumerate()
+                .map(|(square, piece)| by_square[square][piece.unwrap()])
+                .sum::<u32>()
+                + left_over
+        };
+        // faded in from nothing at the threshold, so bringing in the piece that crosses it
+        // does not cost anything
+        let endgame = (pieces.len() - outside.len() * 2) as f32 / pieces.len() as f32;
+        -endgame * (steps - board_state.distance_to_target(player)) as f32
+    }
+
+    fn name(&self) -> String {
+        "assignment".to_owned()
+    }
+}
+
+// the least total cost of giving every row its own column; needs at least as many columns as rows
+fn min_cost_assignment(costs: &[Vec<u32>]) -> u32 {
+    min_cost_assignment_rows(costs)
+        .iter()
+        .enumerate()
+        .map(|(row, column)| costs[row][column.unwrap()])
+        .sum()
+}
+
+// the Hungarian algorithm with row and column potentials, O(rows^2 * columns); returns the column
+// given to every row
+fn min_cost_assignment_rows(costs: &[Vec<u32>]) -> Vec<Option<usize>> {
+    let rows = costs.len();
+    let columns = costs.first().map_or(0, |row| row.len());
+    // index 0 stands for no row or column, the real ones start at 1
+    let mut row_potential = vec![0i64; rows + 1];
+    let mut column_potential = vec![0i64; columns + 1];
+    let mut column_row = vec![0usize; columns + 1];
+    let mut previous_column = vec![0usize; columns + 1];
+    for row in 1..=rows {
+        column_row[0] = row;
+        let mut column = 0;
+        let mut slack = vec![i64::MAX; columns + 1];
+        let mut used = vec![false; columns + 1];
+        loop {
+            used[column] = true;
+            let current_row = column_row[column];
+            let mut delta = i64::MAX;
+            let mut next_column = 0;
+            for other in 1..=columns {
+                if used[other] {
+                    continue;
+                }
+                let reduced = costs[current_row - 1][other - 1] as i64
+                    - row_potential[current_row]
+                    - column_potential[other];
+                if reduced < slack[other] {
+                    slack[other] = reduced;
+                    previous_column[other] = column;
+                }
+                if slack[other] < delta {
+                    delta = slack[other];
+                    next_column = other;
+                }
+            }
+            for other in 0..=columns {
+                if used[other] {
+                    row_potential[column_row[other]] += delta;
+                    column_potential[other] -= delta;
+                } else {
+                    slack[other] -= delta;
+                }
+            }
+            column = next_column;
+            if column_row[column] == 0 {
+                break;
+            }
+        }
+        // flip the assignments along the augmenting path
+        while column != 0 {
+            let previous = previous_column[column];
+            column_row[column] = column_row[previous];
+            column = previous;
+        }
+    }
+    let mut assigned = vec![None; rows];
+    for column in 1..=columns {
+        if column_row[column] != 0 {
+            assigned[column_row[column] - 1] = Some(column - 1);
+        }
+    }
+    assigned
+}
+
+impl Feature for Noise {
+    fn value(&mut self, _: &BoardState, _: Player, _: &ProximityTables) -> f32 {
+        self.rng.gen_range(-100.0..100.0)
+    }
+
+    fn name(&self) -> String {
+        "noise".to_owned()
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+}
+
+// looks a feature up by the name it reports
+pub fn named(name: &str) -> Option<Box<dyn Feature>> {
+    all().into_iter().find(|feature| feature.name() == name)
+}
+
+// one of every feature
+pub fn all() -> Vec<Box<dyn Feature>> {
+    vec![
+        Box::new(Progress),
+        Box::new(LeadingPiece),
+        Box::new(Straggler),
+        Box::new(HomeOccupancy),
+        Box::new(TargetOccupancy),
+        Box::new(Edges),
+        Box::new(Border),
+        Box::new(Blocking),
+        Box::new(Mobility),
+        Box::new(MovesLeft::default()),
+        Box::new(Assignment),
+        Box::new(Noise::default()),
+    ]
+}
+
+#[inline]
+fn dont_block_other_players_in_base(
+    piece_coords: &Coords,
+    board_state: &BoardState,
+    evaluating_player: Player,
+) -> f32 {
+    let config = &board_state.config;
+    let mut score = 0.;
+    for direction in config.directions() {
+        let looking_at_coords = Coords {
+            x: piece_coords.x + direction.x,
+            y: piece_coords.y + direction.y,
+        };
+        if config.is_in_board(looking_at_coords) {
+            for other_player in config.opponents(evaluating_player) {
+                if board_state
+                    .coords(other_player)
+                    .contains(&looking_at_coords)
+                    && config.base(other_player).contains(&looking_at_coords)
+                {
+                    score -= 1.;
+                }
+            }
+        }
+    }
+    score
+}
+
+#[inline]
+fn discourage_edges(piece_coords: &Coords, config: &BoardConfig) -> f32 {
+    if piece_coords.y == 0 || piece_coords.y == config.height - 1 {
+        -0.1
+    } else {
+        0.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decision_tree::DecisionTreeNode;
+    use crate::halma::Position;
+    use crate::heuristic_config::load_heuristic;
+    use crate::heuristics::Heuristic;
+
+    use std::rc::Rc;
+
+    // a heuristic pushed into the clamp scores the start and every move after it the same,
+    // so some first move has to evaluate higher than the start on every board
+    #[test]
+    fn moves_left_heuristic_prefers_progress() {
+        for name in BoardConfig::VARIANTS {
+            let config = Rc::new(BoardConfig::named(name).unwrap());
+            let player = config.players[0];
+            let mut heuristic = load_heuristic("heuristics/moves-left.toml", &config).unwrap();
+            let mut node = DecisionTreeNode::new(Position::new(
+                BoardState::start_position(config.clone()),
+                player,
+            ));
+            let start = heuristic.evaluate(&node.position.board_state, player, 0);
+            node.generate_children();
+            let best = node
+                .children
+                .iter()
+                .map(|child| heuristic.evaluate(&child.position.board_state, player, 0))
+                .fold(f32::NEG_INFINITY, f32::max);
+            assert!(
+                best > start,
+                "{}: {} after the best move, {} at the start",
+                name,
+                best,
+                start
+            );
+        }
+    }
+
+    // the least total over every way of giving the rows distinct columns
+    fn brute_force(costs: &[Vec<u32>], row: usize, used: &mut Vec<bool>) -> u32 {
+        if row == costs.len() {
+            return 0;
+        }
+        let mut best = u32::MAX;
+        for column in 0..used.len() {
+            if !used[column] {
+                used[column] = true;
+                best = best.min(costs[row][column] + brute_force(costs, row + 1, used));
+                used[column] = false;
+            }
+        }
+        best
+    }
+
+    // every row gets its own column and the columns add up to the given cost
+    fn assert_assignment(costs: &[Vec<u32>], cost: u32) {
+        let assigned = min_cost_assignment_rows(costs);
+        let mut columns: Vec<usize> = assigned.iter().map(|column| column.unwrap()).collect();
+        let total: u32 = columns
+            .iter()
+            .enumerate()
+            .map(|(row, column)| costs[row][*column])
+            .sum();
+        columns.sort();
+        columns.dedup();
+        assert_eq!(columns.len(), costs.len(), "{:?} for {:?}", assigned, costs);
+        assert_eq!(total, cost, "{:?} for {:?}", assigned, costs);
+        assert_eq!(min_cost_assignment(costs), cost);
+    }
+
+    #[test]
+    fn assignment_of_small_matrices() {
+        assert_assignment(&[vec![4, 1, 3], vec![2, 0, 5], vec![3, 2, 2]], 5);
+        assert_assignment(&[vec![7]], 7);
+        // the greedy choice of the first row would cost 1 + 10
+        assert_assignment(&[vec![1, 2], vec![1, 10]], 3);
+        // more columns than rows
+        assert_assignment(&[vec![5, 9, 1, 4], vec![3, 8, 1, 6]], 4);
+        // ties everywhere still give every row its own column
+        assert_assignment(&[vec![2, 2, 2], vec![2, 2, 2], vec![2, 2, 2]], 6);
+        assert_assignment(&[vec![0, 0, 3], vec![0, 0, 3]], 0);
+        assert!(min_cost_assignment_rows(&[]).is_empty());
+    }
+
+    #[test]
+    fn assignment_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..200 {
+            let rows = rng.gen_range(1..=5);
+            let columns = rng.gen_range(rows..=7);
+            let costs: Vec<Vec<u32>> = (0..rows)
+                .map(|_| (0..columns).map(|_| rng.gen_range(0..6)).collect())
+                .collect();
+            let cost = brute_force(&costs, 0, &mut vec![false; columns]);
+            assert_assignment(&costs, cost);
+        }
+    }
+
+    // the tables guide the pieces until most of them have arrived
+    #[test]
+    fn assignment_is_zero_before_the_endgame() {
+        for name in BoardConfig::VARIANTS {
+            let board_state =
+                BoardState::start_position(Rc::new(BoardConfig::named(name).unwrap()));
+            let tables = ProximityTables::new(&board_state.config);
+            for player in &board_state.config.players {
+                assert_eq!(
+                    Assignment.value(&board_state, *player, &tables),
+                    0.,
+                    "{}",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn features_are_found_by_name() {
+        let names: Vec<String> = all().iter().map(|feature| feature.name()).collect();
+        for name in &names {
+            assert_eq!(named(name).unwrap().name(), *name);
+            assert_eq!(names.iter().filter(|other| *other == name).count(), 1);
+        }
+        assert!(named("speed").is_none());
+    }
+}
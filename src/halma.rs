@@ -1,114 +1,1315 @@
-use std::error::Error;
+use crate::decision_tree::DecisionTreeNode;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum Tile {
-    Empty,
-    Black,
-    White,
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    error::Error,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    rc::Rc,
+    str::FromStr,
+};
+
+pub type PlayerCoords = Vec<Coords>;
+
+// shape of the board: the neighbours of a square, which coordinates exist and how far apart they are
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Topology {
+    // rectangular Halma board, pieces move in all 8 directions
+    Square,
+    // Chinese Checkers star with camps of `size` rows. Cells use axial hex coordinates
+    // shifted by `2 * size`, so that they fit into a `4 * size + 1` wide square grid
+    Star { size: i8 },
+}
+
+impl Topology {
+    #[inline]
+    pub fn directions(&self) -> &'static [Coords] {
+        match self {
+            Topology::Square => &DIRECTIONS,
+            Topology::Star { .. } => &HEX_DIRECTIONS,
+        }
+    }
+
+    // least number of single steps between two cells on an empty board
+    #[inline]
+    pub fn distance(&self, from: Coords, to: Coords) -> i8 {
+        let dx = to.x - from.x;
+        let dy = to.y - from.y;
+        match self {
+            Topology::Square => dx.abs().max(dy.abs()),
+            Topology::Star { .. } => (dx.abs() + dy.abs() + (dx + dy).abs()) / 2,
+        }
+    }
+
+    // cube coordinates of a star cell, centered on the middle of the board
+    #[inline]
+    fn cube(size: i8, coords: Coords) -> (i8, i8, i8) {
+        let q = coords.x - 2 * size;
+        let r = coords.y - 2 * size;
+        (q, r, -q - r)
+    }
+}
+
+// directions a piece may step and jump in; the star board always uses its six neighbours
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Movement {
+    Orthogonal,
+    AllDirections,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JumpOver {
+    AnyPiece,
+    OwnPieces,
+}
+
+// what happens to a player left without a legal move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Blocked {
+    // the turn goes to the next player
+    Pass,
+    // the blocked player loses, the next player in turn order is declared the winner
+    Loss,
+    Draw,
+}
+
+// variants of the rules, the default being the ones the engine was written for:
+// 8-way moves, jumping over any piece, no leaving the target camp once in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rules {
+    pub movement: Movement,
+    pub jump_over: JumpOver,
+    // whether a piece standing in the target camp may move out of it again
+    pub leave_target: bool,
+    // whether a piece outside of its home camp may move back into it
+    pub enter_home: bool,
+    // a player also wins once every square of the target camp is taken and
+    // at least one of them by their own piece, so pieces left at home cannot block the win
+    pub blocked_target_win: bool,
+    // after this many moves of their own a player has to move pieces out of the home camp
+    // whenever they still have some there
+    pub vacate_home_after: Option<u32>,
+    // the game is drawn once the same position with the same player to move occurs this many times
+    pub repetitions: Option<u32>,
+    // the game is drawn after this many moves without the total distance of all pieces
+    // to their target camps reaching a new minimum
+    pub no_progress_plies: Option<u32>,
+    // the game is drawn after this many moves in total
+    pub ply_cap: Option<u32>,
+    pub blocked: Blocked,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            movement: Movement::AllDirections,
+            jump_over: JumpOver::AnyPiece,
+            leave_target: false,
+            enter_home: true,
+            blocked_target_win: false,
+            vacate_home_after: None,
+            repetitions: None,
+            no_progress_plies: None,
+            ply_cap: None,
+            blocked: Blocked::Pass,
+        }
+    }
+}
+
+// text format: comma separated list of the rules that differ from the default,
+// e.g. `orthogonal,own-jumps,leave-target,no-reentry,blocked-win,blocked=loss,vacate-home=20,ply-cap=400`
+impl Display for Rules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let default = Rules::default();
+        let mut names: Vec<String> = Vec::new();
+        if self.movement != default.movement {
+            names.push(match self.movement {
+                Movement::Orthogonal => "orthogonal".to_owned(),
+                Movement::AllDirections => "all-directions".to_owned(),
+            });
+        }
+        if self.jump_over != default.jump_over {
+            names.push(match self.jump_over {
+                JumpOver::AnyPiece => "any-jumps".to_owned(),
+                JumpOver::OwnPieces => "own-jumps".to_owned(),
+            });
+        }
+        if self.leave_target != default.leave_target {
+            names.push(match self.leave_target {
+                true => "leave-target".to_owned(),
+                false => "stay-in-target".to_owned(),
+            });
+        }
+        if self.enter_home != default.enter_home {
+            names.push(match self.enter_home {
+                true => "reentry".to_owned(),
+                false => "no-reentry".to_owned(),
+            });
+        }
+        if self.blocked_target_win != default.blocked_target_win {
+            names.push(match self.blocked_target_win {
+                true => "blocked-win".to_owned(),
+                false => "full-win".to_owned(),
+            });
+        }
+        if self.blocked != default.blocked {
+            names.push(match self.blocked {
+                Blocked::Pass => "blocked=pass".to_owned(),
+                Blocked::Loss => "blocked=loss".to_owned(),
+                Blocked::Draw => "blocked=draw".to_owned(),
+            });
+        }
+        // limits are off by default and only written when set
+        for (name, limit) in [
+            ("vacate-home", self.vacate_home_after),
+            ("repetition", self.repetitions),
+            ("no-progress", self.no_progress_plies),
+            ("ply-cap", self.ply_cap),
+        ] {
+            if let Some(limit) = limit {
+                names.push(format!("{}={}", name, limit));
+            }
+        }
+        write!(f, "{}", names.join(","))
+    }
 }
-pub type Board = [[Tile; 16]; 16];
 
-pub type PlayerCoords = [Coords; 19];
+impl FromStr for Rules {
+    type Err = Box<dyn Error>;
 
-#[derive(Debug, Clone, Copy)]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Rules::default();
+        for name in s.split(',').map(|name| name.trim()) {
+            if let Some((name, value)) = name.split_once('=') {
+                if name == "blocked" {
+                    rules.blocked = match value {
+                        "pass" => Blocked::Pass,
+                        "loss" => Blocked::Loss,
+                        "draw" => Blocked::Draw,
+                        _ => return Err(format!("Unknown blocked rule: {}", value).into()),
+                    };
+                    continue;
+                }
+                let limit = Some(value.parse()?);
+                match name {
+                    "vacate-home" => rules.vacate_home_after = limit,
+                    "repetition" => rules.repetitions = limit,
+                    "no-progress" => rules.no_progress_plies = limit,
+                    "ply-cap" => rules.ply_cap = limit,
+                    _ => return Err(format!("Unknown rule: {}", name).into()),
+                }
+                continue;
+            }
+            match name {
+                "" => {}
+                "orthogonal" => rules.movement = Movement::Orthogonal,
+                "all-directions" => rules.movement = Movement::AllDirections,
+                "any-jumps" => rules.jump_over = JumpOver::AnyPiece,
+                "own-jumps" => rules.jump_over = JumpOver::OwnPieces,
+                "leave-target" => rules.leave_target = true,
+                "stay-in-target" => rules.leave_target = false,
+                "reentry" => rules.enter_home = true,
+                "no-reentry" => rules.enter_home = false,
+                "blocked-win" => rules.blocked_target_win = true,
+                "full-win" => rules.blocked_target_win = false,
+                _ => return Err(format!("Unknown rule: {}", name).into()),
+            }
+        }
+        Ok(rules)
+    }
+}
+
+// dimensions of the board and the camps of all players; every player starts
+// in one corner and has to reach the opposite one. On the square board black's camp is
+// in the top left, white's in the bottom right, red's in the top right and green's in the
+// bottom left. On the star going clockwise from the top are black, red, blue, white, green, yellow
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardConfig {
+    pub topology: Topology,
+    pub width: i8,
+    pub height: i8,
+    // how many squares of the camp lie in each row, starting from the corner
+    pub camp_rows: Vec<i8>,
+    // players taking part, in turn order
+    pub players: Vec<Player>,
+    // camps of every corner, indexed by `Player::index`; the first square of a camp is its corner
+    pub bases: Vec<Vec<Coords>>,
+    // squares every player has to reach, indexed by `Player::index`; the opposite camp
+    // unless a puzzle or endgame study sets its own
+    pub targets: Vec<Vec<Coords>>,
+    pub rules: Rules,
+}
+
+impl BoardConfig {
+    pub fn new(width: i8, height: i8, camp_rows: &[i8], player_count: usize) -> Self {
+        let bases: Vec<Vec<Coords>> = Player::ALL
+            .iter()
+            .map(|player| {
+                let mut base = Vec::new();
+                for (row, row_length) in camp_rows.iter().enumerate() {
+                    for column in 0..*row_length {
+                        let (x, y) = match player {
+                            Player::Black => (column, row as i8),
+                            Player::White => (width - 1 - column, height - 1 - row as i8),
+                            Player::Red => (width - 1 - column, row as i8),
+                            Player::Green => (column, height - 1 - row as i8),
+                            // the square board only has four corners
+                            Player::Blue | Player::Yellow => continue,
+                        };
+                        base.push(Coords { x, y });
+                    }
+                }
+                base
+            })
+            .collect();
+        // going around the board clockwise
+        let players = match player_count {
+            4 => vec![Player::Black, Player::Red, Player::White, Player::Green],
+            _ => vec![Player::Black, Player::White],
+        };
+        BoardConfig {
+            topology: Topology::Square,
+            width,
+            height,
+            camp_rows: camp_rows.to_vec(),
+            players,
+            targets: Self::opposite_camps(&bases),
+            bases,
+            rules: Rules::default(),
+        }
+    }
+
+    // Chinese Checkers star with triangular camps of `size` rows, `size` 4 being the usual 121 hole board;
+    // 2, 3, 4 or 6 players take part
+    pub fn star(size: i8, player_count: usize) -> Self {
+        let side = 4 * size + 1;
+        let bases: Vec<Vec<Coords>> = Player::ALL
+            .iter()
+            .map(|player| {
+                // the corner of the camp, in cube coordinates
+                let tip = match player {
+                    Player::Black => (size, -2 * size, size),
+                    Player::Red => (2 * size, -size, -size),
+                    Player::Blue => (size, size, -2 * size),
+                    Player::White => (-size, 2 * size, -size),
+                    Player::Green => (-2 * size, size, size),
+                    Player::Yellow => (-size, -size, 2 * size),
+                };
+                let mut base = Vec::new();
+                for y in 0..side {
+                    for x in 0..side {
+                        let coords = Coords { x, y };
+                        let (q, r, s) = Topology::cube(size, coords);
+                        let in_camp = match player {
+                            Player::Black => r < -size,
+                            Player::Red => q > size,
+                            Player::Blue => s < -size,
+                            Player::White => r > size,
+                            Player::Green => q < -size,
+                            Player::Yellow => s > size,
+                        };
+                        if in_camp && Self::is_in_star(size, coords) {
+                            base.push(coords);
+                        }
+                    }
+                }
+                let tip = Coords {
+                    x: tip.0 + 2 * size,
+                    y: tip.1 + 2 * size,
+                };
+                let topology = Topology::Star { size };
+                base.sort_by_key(|coords| topology.distance(tip, *coords));
+                base
+            })
+            .collect();
+        // going around the board clockwise
+        let players = match player_count {
+            3 => vec![Player::Black, Player::Blue, Player::Green],
+            4 => vec![Player::Black, Player::Red, Player::White, Player::Green],
+            6 => vec![
+                Player::Black,
+                Player::Red,
+                Player::Blue,
+                Player::White,
+                Player::Green,
+                Player::Yellow,
+            ],
+            _ => vec![Player::Black, Player::White],
+        };
+        BoardConfig {
+            topology: Topology::Star { size },
+            width: side,
+            height: side,
+            camp_rows: (1..=size).collect(),
+            players,
+            targets: Self::opposite_camps(&bases),
+            bases,
+            rules: Rules::default(),
+        }
+    }
+
+    // the star is made of two big triangles laid over each other
+    fn is_in_star(size: i8, coords: Coords) -> bool {
+        let (q, r, s) = Topology::cube(size, coords);
+        let upper = q <= size && r <= size && s <= size;
+        let lower = q >= -size && r >= -size && s >= -size;
+        upper || lower
+    }
+
+    // 16x16 with 19 pieces per side
+    pub fn standard() -> Self {
+        Self::new(16, 16, &[5, 5, 4, 3, 2], 2)
+    }
+
+    // names accepted by `named`, each playable from its start position without a board file
+    pub const VARIANTS: [&'static str; 10] = [
+        "16x16", "16x16-4p", "8x8-10", "8x8-13", "10x10", "10x10-4p", "star", "star-3p", "star-4p",
+        "star-6p",
+    ];
+
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "16x16" => Some(Self::standard()),
+            "16x16-4p" => Some(Self::new(16, 16, &[4, 4, 3, 2], 4)),
+            "8x8-10" => Some(Self::new(8, 8, &[4, 3, 2, 1], 2)),
+            "8x8-13" => Some(Self::new(8, 8, &[4, 4, 3, 2], 2)),
+            "10x10" => Some(Self::new(10, 10, &[5, 4, 3, 2, 1], 2)),
+            "10x10-4p" => Some(Self::new(10, 10, &[4, 3, 2, 1], 4)),
+            "star" | "star-2p" => Some(Self::star(4, 2)),
+            "star-3p" => Some(Self::star(4, 3)),
+            "star-4p" => Some(Self::star(4, 4)),
+            "star-6p" => Some(Self::star(4, 6)),
+            _ => None,
+        }
+    }
+
+    // classic camp layout for the given board size; the 8x8 board is played with 10 or 13 pieces
+    // and four players get smaller camps so that they do not touch each other
+    fn default_camp_rows(
+        width: i8,
+        height: i8,
+        pieces: usize,
+        player_count: usize,
+    ) -> Option<Vec<i8>> {
+        match (width, height, pieces, player_count) {
+            (16, 16, _, 4) => Some(vec![4, 4, 3, 2]),
+            (16, 16, _, _) => Some(vec![5, 5, 4, 3, 2]),
+            (8, 8, 13, 2) => Some(vec![4, 4, 3, 2]),
+            (8, 8, _, 2) => Some(vec![4, 3, 2, 1]),
+            (10, 10, _, 4) => Some(vec![4, 3, 2, 1]),
+            (10, 10, _, _) => Some(vec![5, 4, 3, 2, 1]),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_in_board(&self, coords: Coords) -> bool {
+        let in_grid = (coords.x < self.width)
+            && (coords.x > -1)
+            && (coords.y < self.height)
+            && (coords.y > -1);
+        match self.topology {
+            Topology::Square => in_grid,
+            Topology::Star { size } => in_grid && Self::is_in_star(size, coords),
+        }
+    }
+
+    #[inline]
+    pub fn directions(&self) -> &'static [Coords] {
+        match (self.topology, self.rules.movement) {
+            (Topology::Square, Movement::Orthogonal) => &ORTHOGONAL_DIRECTIONS,
+            _ => self.topology.directions(),
+        }
+    }
+
+    // whether the camp rules let `player` move a piece from `from` to `to`
+    pub fn allows_move(&self, player: Player, from: Coords, to: Coords) -> bool {
+        let target = self.target(player);
+        if !self.rules.leave_target && target.contains(&from) && !target.contains(&to) {
+            return false;
+        }
+        let home = self.base(player);
+        if !self.rules.enter_home && !home.contains(&from) && home.contains(&to) {
+            return false;
+        }
+        true
+    }
+
+    #[inline]
+    pub fn distance(&self, from: Coords, to: Coords) -> i8 {
+        self.topology.distance(from, to)
+    }
+
+    // column of a cell in the text board format; star rows are shifted by half a cell each
+    // and have a gap between neighbouring cells
+    #[inline]
+    pub fn text_column(&self, coords: Coords) -> usize {
+        match self.topology {
+            Topology::Square => coords.x as usize,
+            Topology::Star { size } => (2 * coords.x + coords.y - 3 * size) as usize,
+        }
+    }
+
+    // the cell drawn at a given column of a text row, if any
+    pub fn text_cell(&self, column: usize, y: i8) -> Option<Coords> {
+        let x = match self.topology {
+            Topology::Square => column as i8,
+            Topology::Star { size } => {
+                let shifted = column as i8 + 3 * size - y;
+                if shifted % 2 != 0 {
+                    return None;
+                }
+                shifted / 2
+            }
+        };
+        let coords = Coords { x, y };
+        match self.is_in_board(coords) {
+            true => Some(coords),
+            false => None,
+        }
+    }
+
+    pub fn text_width(&self) -> usize {
+        match self.topology {
+            Topology::Square => self.width as usize,
+            Topology::Star { size } => (6 * size + 1) as usize,
+        }
+    }
+
+    // the camp a player starts in
+    #[inline]
+    pub fn base(&self, player: Player) -> &[Coords] {
+        &self.bases[player.index()]
+    }
+
+    // the camp a player has to fill to win
+    #[inline]
+    pub fn target(&self, player: Player) -> &[Coords] {
+        &self.targets[player.index()]
+    }
+
+    fn opposite_camps(bases: &[Vec<Coords>]) -> Vec<Vec<Coords>> {
+        Player::ALL
+            .iter()
+            .map(|player| bases[player.opposite().index()].clone())
+            .collect()
+    }
+
+    // position of the player in the turn order
+    #[inline]
+    pub fn seat(&self, player: Player) -> usize {
+        self.players.iter().position(|p| *p == player).unwrap_or(0)
+    }
+
+    // whose turn it is after `player` moved
+    pub fn next_player(&self, player: Player) -> Player {
+        self.players[(self.seat(player) + 1) % self.players.len()]
+    }
+
+    // whose turn it was before `player`
+    pub fn previous_player(&self, player: Player) -> Player {
+        let count = self.players.len();
+        self.players[(self.seat(player) + count - 1) % count]
+    }
+
+    pub fn opponents(&self, player: Player) -> impl Iterator<Item = Player> + '_ {
+        self.players.iter().copied().filter(move |p| *p != player)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct BoardState {
-    pub black_coords: PlayerCoords,
-    pub white_coords: PlayerCoords,
+    pub config: Rc<BoardConfig>,
+    // pieces of every player, indexed by `Player::index`; empty for players not in the game
+    pub pieces: Vec<PlayerCoords>,
 }
 
 pub fn board_state_to_string(board_state: &BoardState) -> String {
+    let config = &board_state.config;
     let mut base_string = "".to_owned();
-    for y in 0..16 {
-        for x in 0..16 {
-            if board_state.black_coords.contains(&Coords { x, y }) {
-                base_string += "1";
-            } else if board_state.white_coords.contains(&Coords { x, y }) {
-                base_string += "2";
-            } else {
-                base_string += "0";
+    // the camp line is left out whenever reading the board back would guess the same camps
+    let pieces = board_state
+        .pieces
+        .iter()
+        .map(|pieces| pieces.len())
+        .max()
+        .unwrap_or(0);
+    if let Topology::Star { .. } = config.topology {
+        base_string += "star\n";
+    } else if BoardConfig::default_camp_rows(
+        config.width,
+        config.height,
+        pieces,
+        config.players.len(),
+    )
+    .as_ref()
+        != Some(&config.camp_rows)
+    {
+        let rows: Vec<String> = config.camp_rows.iter().map(|row| row.to_string()).collect();
+        base_string += &format!("camp {}\n", rows.join(","));
+    }
+    if config.rules != Rules::default() {
+        base_string += &format!("rules {}\n", config.rules);
+    }
+    for player in &config.players {
+        let target = config.target(*player);
+        if target != config.base(player.opposite()) {
+            let squares: Vec<String> = target.iter().map(|coords| coords.to_string()).collect();
+            base_string += &format!("target {} {}\n", player.name(), squares.join(","));
+        }
+    }
+    for y in 0..config.height {
+        let mut line = vec![' '; config.text_width()];
+        for x in 0..config.width {
+            let coords = Coords { x, y };
+            if config.is_in_board(coords) {
+                line[config.text_column(coords)] = match board_state.player_at(coords) {
+                    Some(player) => player.digit(),
+                    None => '0',
+                };
             }
         }
+        base_string += line.iter().collect::<String>().trim_end();
         base_string += "\n";
     }
-    return base_string;
-}
-
-pub fn board_state_from_str(board_str: &String) -> Result<BoardState, Box<dyn Error>> {
-    let split_str = board_str.split('\n');
-    let mut black_coords: Vec<Coords> = Vec::with_capacity(19);
-    let mut white_coords: Vec<Coords> = Vec::with_capacity(19);
-    split_str.enumerate().for_each(|(row_idx, line)| {
-        line.chars()
-            .enumerate()
-            .for_each(|(char_idx, char)| match char {
-                '0' => {}
-                '1' => {
-                    black_coords.push(Coords {
-                        x: char_idx as i8,
-                        y: row_idx as i8,
-                    });
-                }
-                '2' => {
-                    white_coords.push(Coords {
-                        x: char_idx as i8,
-                        y: row_idx as i8,
-                    });
+    base_string
+}
+
+impl BoardState {
+    // every player standing in their own camp
+    pub fn start_position(config: Rc<BoardConfig>) -> Self {
+        let mut pieces = vec![Vec::new(); Player::ALL.len()];
+        for player in &config.players {
+            pieces[player.index()] = config.base(*player).to_vec();
+        }
+        BoardState { config, pieces }
+    }
+
+    #[inline]
+    pub fn coords(&self, player: Player) -> &PlayerCoords {
+        &self.pieces[player.index()]
+    }
+
+    #[inline]
+    pub fn coords_mut(&mut self, player: Player) -> &mut PlayerCoords {
+        &mut self.pieces[player.index()]
+    }
+
+    pub fn player_at(&self, coords: Coords) -> Option<Player> {
+        self.config
+            .players
+            .iter()
+            .copied()
+            .find(|player| self.coords(*player).contains(&coords))
+    }
+
+    #[inline]
+    pub fn is_empty(&self, coords: Coords) -> bool {
+        !self.pieces.iter().any(|pieces| pieces.contains(&coords))
+    }
+
+    // whether `player` may jump over whatever stands on `coords`
+    #[inline]
+    pub fn can_jump_over(&self, coords: Coords, player: Player) -> bool {
+        match self.config.rules.jump_over {
+            JumpOver::AnyPiece => !self.is_empty(coords),
+            JumpOver::OwnPieces => self.coords(player).contains(&coords),
+        }
+    }
+
+    // every piece of the player stands in the target camp, or the target is all taken by
+    // the player's pieces when they have more pieces than it has squares. With `blocked_target_win`
+    // it is enough that the target is full and holds at least one of the player's pieces
+    pub fn is_won(&self, player: Player) -> bool {
+        let target = self.config.target(player);
+        let my_coords = self.coords(player);
+        let in_target = my_coords
+            .iter()
+            .filter(|coords| target.contains(coords))
+            .count();
+        if in_target == my_coords.len().min(target.len()) {
+            return true;
+        }
+        self.config.rules.blocked_target_win
+            && target.iter().all(|coords| !self.is_empty(*coords))
+            && my_coords.iter().any(|coords| target.contains(coords))
+    }
+
+    // whether the `vacate_home_after` rule forces the player to take pieces out of the home camp
+    // after having made `moves_made` moves
+    pub fn must_vacate_home(&self, player: Player, moves_made: u32) -> bool {
+        match self.config.rules.vacate_home_after {
+            Some(limit) if moves_made >= limit => {
+                let home = self.config.base(player);
+                self.coords(player)
+                    .iter()
+                    .any(|coords| home.contains(coords))
+            }
+            _ => false,
+        }
+    }
+
+    // pieces of every player as (y, x) pairs in reading order, the same for every
+    // order the pieces happen to be stored in
+    pub fn sorted_pieces(&self) -> Vec<Vec<(i8, i8)>> {
+        self.pieces
+            .iter()
+            .map(|pieces| {
+                let mut sorted: Vec<(i8, i8)> =
+                    pieces.iter().map(|coords| (coords.y, coords.x)).collect();
+                sorted.sort_unstable();
+                sorted
+            })
+            .collect()
+    }
+
+    // identifies the position regardless of the order the pieces are stored in
+    pub fn position_hash(&self, to_move: Player) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        to_move.hash(&mut hasher);
+        self.sorted_pieces().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // sum over the player's pieces of the steps left to the nearest square of the target camp
+    pub fn distance_to_target(&self, player: Player) -> u32 {
+        let target = self.config.target(player);
+        self.coords(player)
+            .iter()
+            .map(|coords| {
+                target
+                    .iter()
+                    .map(|square| self.config.distance(*coords, *square) as u32)
+                    .min()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    pub fn total_distance_to_targets(&self) -> u32 {
+        self.config
+            .players
+            .iter()
+            .map(|player| self.distance_to_target(*player))
+            .sum()
+    }
+
+    // handicap: takes away `count` of the player's pieces, starting with the ones
+    // deepest in the home camp; at least one piece always stays on the board
+    pub fn remove_pieces(&mut self, player: Player, count: usize) {
+        let config = self.config.clone();
+        let pieces = &mut self.pieces[player.index()];
+        if let Some(corner) = config.base(player).first() {
+            pieces.sort_by_key(|coords| config.distance(*corner, *coords));
+        }
+        let count = count.min(pieces.len().saturating_sub(1));
+        pieces.drain(..count);
+    }
+
+    // moves whichever piece stands on `player_move.from`
+    pub fn apply_move(&mut self, player_move: &Move) {
+        for coords in self.pieces.iter_mut().flatten() {
+            if *coords == player_move.from {
+                *coords = player_move.to;
+                return;
+            }
+        }
+    }
+}
+
+// board format: one line per row with 0 for an empty square and the player's digit
+// (1 black, 2 white, 3 red, 4 green, 5 blue, 6 yellow) for a piece; the size is taken from
+// the text, four players are assumed once red or green pieces show up and an optional
+// `camp 4,3,2,1` first line sets the camp shape, a `rules own-jumps,...` line the rule variants
+// and `target white a1,b1,a2` lines the squares a player has to reach instead of the opposite camp.
+// Players may have any number of pieces, as long as every player in the game has at least one.
+// A `star` first line switches to the Chinese Checkers board, drawn as a star of
+// cells separated by spaces with every row shifted by half a cell
+pub fn board_state_from_str(board_str: &str) -> Result<BoardState, Box<dyn Error>> {
+    let mut camp_rows: Option<Vec<i8>> = None;
+    let mut star = false;
+    let mut rules = Rules::default();
+    let mut targets: Vec<(Player, Vec<Coords>)> = Vec::new();
+    let mut rows: Vec<&str> = Vec::with_capacity(16);
+    for line in board_str.lines() {
+        let trimmed = line.trim();
+        if let Some(camp) = trimmed.strip_prefix("camp") {
+            camp_rows = Some(
+                camp.split(',')
+                    .map(|row| row.trim().parse())
+                    .collect::<Result<Vec<i8>, _>>()?,
+            );
+        } else if let Some(rule_names) = trimmed.strip_prefix("rules") {
+            rules = rule_names.parse()?;
+        } else if let Some(target) = trimmed.strip_prefix("target") {
+            let (player, squares) = target
+                .trim()
+                .split_once(' ')
+                .ok_or(format!("Invalid target line: {}", trimmed))?;
+            targets.push((
+                player.parse()?,
+                squares
+                    .split(',')
+                    .map(|square| square.trim().parse())
+                    .collect::<Result<Vec<Coords>, _>>()?,
+            ));
+        } else if trimmed == "star" {
+            star = true;
+        } else if trimmed.starts_with("turn") {
+            // read by `position_from_str`
+        } else if !trimmed.is_empty() {
+            rows.push(if star { line.trim_end() } else { trimmed });
+        }
+    }
+
+    let mut pieces: Vec<PlayerCoords> = vec![Vec::with_capacity(19); Player::ALL.len()];
+    let height = rows.len() as i8;
+    let mut width = 0;
+    let star_size = (height - 1) / 4;
+    for (y, line) in rows.iter().enumerate() {
+        let y = y as i8;
+        for (char_idx, char) in line.chars().enumerate() {
+            let x = if star {
+                // undo the half cell shift of every row
+                let shifted = char_idx as i8 + 3 * star_size - y;
+                if shifted % 2 != 0 {
+                    continue;
                 }
-                _ => {}
-            });
-    });
+                shifted / 2
+            } else {
+                char_idx as i8
+            };
+            if let Some(player) = Player::from_digit(char) {
+                pieces[player.index()].push(Coords { x, y });
+            }
+        }
+        width = width.max(line.chars().count() as i8);
+    }
+
+    let mut config = if star {
+        let player_count = pieces.iter().filter(|pieces| !pieces.is_empty()).count();
+        BoardConfig::star(star_size, player_count)
+    } else {
+        let player_count =
+            if pieces[Player::Red.index()].is_empty() && pieces[Player::Green.index()].is_empty() {
+                2
+            } else {
+                4
+            };
+        let camp_rows = camp_rows
+            .or_else(|| {
+                BoardConfig::default_camp_rows(
+                    width,
+                    height,
+                    pieces.iter().map(|pieces| pieces.len()).max().unwrap_or(0),
+                    player_count,
+                )
+            })
+            .ok_or(format!(
+                "No camp layout known for a {}x{} board, add a `camp` line",
+                width, height
+            ))?;
+        BoardConfig::new(width, height, &camp_rows, player_count)
+    };
+    config.rules = rules;
+    for (player, target) in targets {
+        if let Some(coords) = target.iter().find(|coords| !config.is_in_board(**coords)) {
+            return Err(format!("{:?} target outside of the board at {}", player, coords).into());
+        }
+        config.targets[player.index()] = target;
+    }
+    for player in Player::ALL {
+        let found = pieces[player.index()].len();
+        if config.players.contains(&player) && found == 0 {
+            return Err(format!("{:?} has no pieces", player).into());
+        }
+        if let Some(coords) = pieces[player.index()]
+            .iter()
+            .find(|coords| !config.is_in_board(**coords))
+        {
+            return Err(format!("{:?} piece outside of the board at {}", player, coords).into());
+        }
+    }
     Ok(BoardState {
-        black_coords: black_coords.as_slice().try_into()?,
-        white_coords: white_coords.as_slice().try_into()?,
+        config: Rc::new(config),
+        pieces,
     })
 }
 
-#[inline]
-pub fn index_board(board: &Board, coords: Coords) -> Tile {
-    board[coords.y as usize][coords.x as usize]
+// a board with an optional `turn white` first line naming the player to move,
+// the first player in turn order when it is missing
+pub fn position_from_str(position_str: &str) -> Result<Position, Box<dyn Error>> {
+    let board_state = board_state_from_str(position_str)?;
+    let to_move = match position_str
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("turn"))
+    {
+        Some(player) => {
+            let player: Player = player.trim().parse()?;
+            if !board_state.config.players.contains(&player) {
+                return Err(format!("{:?} does not take part in this game", player).into());
+            }
+            player
+        }
+        None => board_state.config.players[0],
+    };
+    Ok(Position::new(board_state, to_move))
+}
+
+pub fn position_to_string(position: &Position) -> String {
+    let mut base_string = "".to_owned();
+    if position.to_move != position.board_state.config.players[0] {
+        base_string += &format!("turn {}\n", position.to_move.name());
+    }
+    base_string + &board_state_to_string(&position.board_state)
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum Player {
     Black,
     White,
+    Red,
+    Green,
+    Blue,
+    Yellow,
 }
 
 impl Player {
-    pub fn other(&self) -> Player {
+    pub const ALL: [Player; 6] = [
+        Player::Black,
+        Player::White,
+        Player::Red,
+        Player::Green,
+        Player::Blue,
+        Player::Yellow,
+    ];
+
+    #[inline]
+    pub fn index(&self) -> usize {
+        match self {
+            Player::Black => 0,
+            Player::White => 1,
+            Player::Red => 2,
+            Player::Green => 3,
+            Player::Blue => 4,
+            Player::Yellow => 5,
+        }
+    }
+
+    // the player whose camp lies in the opposite corner
+    pub fn opposite(&self) -> Player {
         match self {
             Player::Black => Player::White,
             Player::White => Player::Black,
+            Player::Red => Player::Green,
+            Player::Green => Player::Red,
+            Player::Blue => Player::Yellow,
+            Player::Yellow => Player::Blue,
+        }
+    }
+
+    // name used in board headers and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Player::Black => "black",
+            Player::White => "white",
+            Player::Red => "red",
+            Player::Green => "green",
+            Player::Blue => "blue",
+            Player::Yellow => "yellow",
+        }
+    }
+
+    // symbol used in board files
+    pub fn digit(&self) -> char {
+        (b'1' + self.index() as u8) as char
+    }
+
+    pub fn from_digit(digit: char) -> Option<Player> {
+        Player::ALL
+            .iter()
+            .copied()
+            .find(|player| player.digit() == digit)
+    }
+}
+
+impl FromStr for Player {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "black" | "1" => Ok(Player::Black),
+            "white" | "2" => Ok(Player::White),
+            "red" | "3" => Ok(Player::Red),
+            "green" | "4" => Ok(Player::Green),
+            "blue" | "5" => Ok(Player::Blue),
+            "yellow" | "6" => Ok(Player::Yellow),
+            _ => Err(format!("Unknown player: {}", s).into()),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GameState {
-    Start(Player),
-    Moved(Player),
+    InProgress,
     Won(Player),
+    Draw(DrawReason),
+}
+
+// a board together with whose turn it is, how many moves were played to reach it
+// and the last of them
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub board_state: BoardState,
+    pub to_move: Player,
+    pub ply: u32,
+    pub last_move: Option<Move>,
+}
+
+impl Position {
+    pub fn new(board_state: BoardState, to_move: Player) -> Self {
+        Position {
+            board_state,
+            to_move,
+            ply: 0,
+            last_move: None,
+        }
+    }
+
+    // the position after the side to move plays `player_move`, `None` being a pass
+    pub fn play(&self, player_move: Option<Move>) -> Position {
+        let mut board_state = self.board_state.clone();
+        if let Some(player_move) = &player_move {
+            board_state.apply_move(player_move);
+        }
+        Position {
+            to_move: board_state.config.next_player(self.to_move),
+            board_state,
+            ply: self.ply + 1,
+            last_move: player_move,
+        }
+    }
+
+    // the player who made the last move
+    pub fn moved(&self) -> Player {
+        self.board_state.config.previous_player(self.to_move)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DrawReason {
+    Repetition,
+    NoProgress,
+    PlyCap,
+    // the round limit ran out with no player far enough ahead
+    Adjudication,
+    // a player had no legal move under `Blocked::Draw`
+    Blocked,
+}
+
+impl Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawReason::Repetition => write!(f, "repetition"),
+            DrawReason::NoProgress => write!(f, "no-progress"),
+            DrawReason::PlyCap => write!(f, "ply-cap"),
+            DrawReason::Adjudication => write!(f, "adjudication"),
+            DrawReason::Blocked => write!(f, "blocked"),
+        }
+    }
+}
+
+impl FromStr for DrawReason {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "repetition" => Ok(DrawReason::Repetition),
+            "no-progress" => Ok(DrawReason::NoProgress),
+            "ply-cap" => Ok(DrawReason::PlyCap),
+            "adjudication" => Ok(DrawReason::Adjudication),
+            "blocked" => Ok(DrawReason::Blocked),
+            _ => Err(format!("Unknown draw reason: {}", s).into()),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+// keeps the history of a game needed to detect the draws enabled in the rules
+pub struct DrawTracker {
+    // how many times each position hash occurred
+    seen: HashMap<u64, u32>,
+    best_distance: u32,
+    plies_without_progress: u32,
+    plies: u32,
+}
+
+impl DrawTracker {
+    pub fn new(position: &Position) -> Self {
+        let board_state = &position.board_state;
+        let mut seen = HashMap::new();
+        seen.insert(board_state.position_hash(position.to_move), 1);
+        DrawTracker {
+            seen,
+            best_distance: board_state.total_distance_to_targets(),
+            plies_without_progress: 0,
+            plies: 0,
+        }
+    }
+
+    // records the position reached by the next move of the game
+    pub fn update(&mut self, position: &Position) -> Option<DrawReason> {
+        let board_state = &position.board_state;
+        let rules = &board_state.config.rules;
+        self.plies += 1;
+        let occurrences = self
+            .seen
+            .entry(board_state.position_hash(position.to_move))
+            .or_insert(0);
+        *occurrences += 1;
+        let distance = board_state.total_distance_to_targets();
+        if distance < self.best_distance {
+            self.best_distance = distance;
+            self.plies_without_progress = 0;
+        } else {
+            self.plies_without_progress += 1;
+        }
+        if rules.repetitions.is_some_and(|limit| *occurrences >= limit) {
+            Some(DrawReason::Repetition)
+        } else if rules
+            .no_progress_plies
+            .is_some_and(|limit| self.plies_without_progress >= limit)
+        {
+            Some(DrawReason::NoProgress)
+        } else if rules.ply_cap.is_some_and(|limit| self.plies >= limit) {
+            Some(DrawReason::PlyCap)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Coords {
     pub x: i8,
     pub y: i8,
 }
 
-impl PartialEq for Coords {
-    fn eq(&self, other: &Self) -> bool {
-        self.x == other.x && self.y == other.y
+impl Display for Coords {
+    // algebraic notation: column letter, then 1-based row number
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", (b'a' + self.x as u8) as char, self.y + 1)
     }
 }
 
-impl Coords {
-    #[inline]
-    pub fn is_in_board(&self) -> bool {
-        (self.x < 16) && (self.x > -1) && (self.y < 16) && (self.y > -1)
+impl FromStr for Coords {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let column = chars.next().ok_or("Empty coordinates")?;
+        if !column.is_ascii_lowercase() {
+            return Err(format!("Invalid column in coordinates: {}", s).into());
+        }
+        let row: i8 = chars.as_str().parse()?;
+        Ok(Coords {
+            x: (column as u8 - b'a') as i8,
+            y: row - 1,
+        })
+    }
+}
+
+// a single move of one piece; `path` holds every square the piece landed on,
+// so for a step it is just `[to]` and for a jump chain it lists each hop
+#[derive(Debug, Clone)]
+pub struct Move {
+    pub from: Coords,
+    pub to: Coords,
+    pub path: Vec<Coords>,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.from)?;
+        for point in &self.path {
+            write!(f, "-{}", point)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Move {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut points = s.trim().split('-');
+        let from: Coords = points.next().ok_or("Empty move")?.parse()?;
+        let path = points
+            .map(|point| point.parse())
+            .collect::<Result<Vec<Coords>, _>>()?;
+        let to = *path
+            .last()
+            .ok_or(format!("Move without destination: {}", s))?;
+        Ok(Move { from, to, path })
+    }
+}
+
+// the starting position and every move played from it, enough to replay a whole game
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub start: Position,
+    pub moves: Vec<Move>,
+    // `Won` or `Draw` once the game is over
+    pub result: Option<GameState>,
+}
+
+impl GameRecord {
+    pub fn new(start: Position) -> Self {
+        GameRecord {
+            start,
+            moves: Vec::new(),
+            result: None,
+        }
+    }
+
+    // every position of the game together with the move that led to it
+    pub fn positions(&self) -> Vec<(BoardState, Option<&Move>)> {
+        let mut board_state = self.start.board_state.clone();
+        let mut positions = vec![(board_state.clone(), None)];
+        for player_move in &self.moves {
+            board_state.apply_move(player_move);
+            positions.push((board_state.clone(), Some(player_move)));
+        }
+        positions
+    }
+
+    // every move has to be on the board and legal for the player whose turn it is, with
+    // blocked players passing in between, as passes are not recorded
+    fn check_moves(&self) -> Result<(), Box<dyn Error>> {
+        let config = &self.start.board_state.config;
+        let mut node = DecisionTreeNode::new(self.start.clone());
+        for (idx, player_move) in self.moves.iter().enumerate() {
+            let number = idx + 1;
+            let mut points = std::iter::once(&player_move.from).chain(&player_move.path);
+            if let Some(outside) = points.find(|coords| !config.is_in_board(**coords)) {
+                return Err(format!(
+                    "Move {} {} leaves the board at {}",
+                    number, player_move, outside
+                )
+                .into());
+            }
+            for _ in 0..config.players.len() {
+                node.generate_children();
+                let passes =
+                    node.children.len() == 1 && node.children[0].position.last_move.is_none();
+                if !passes {
+                    break;
+                }
+                node = node.children.swap_remove(0);
+            }
+            if node.game_state != GameState::InProgress {
+                return Err(format!(
+                    "Move {} {} comes after the end of the game",
+                    number, player_move
+                )
+                .into());
+            }
+            let player = node.position.to_move;
+            if !node
+                .position
+                .board_state
+                .coords(player)
+                .contains(&player_move.from)
+            {
+                return Err(format!(
+                    "Move {} {}: {} has no piece on {}",
+                    number,
+                    player_move,
+                    player.name(),
+                    player_move.from
+                )
+                .into());
+            }
+            let played = node.children.iter().position(|child| {
+                child.position.last_move.as_ref().is_some_and(|legal| {
+                    legal.from == player_move.from && legal.to == player_move.to
+                })
+            });
+            match played {
+                Some(child) => node = node.children.swap_remove(child),
+                None => {
+                    return Err(format!(
+                        "Move {} {} is not legal for {}",
+                        number,
+                        player_move,
+                        player.name()
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(())
     }
 }
 
+// record format: the starting position, an empty line, then one move per line
+// and a last `result won black` or `result draw repetition` line for finished games
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", position_to_string(&self.start))?;
+        for player_move in &self.moves {
+            writeln!(f, "{}", player_move)?;
+        }
+        match self.result {
+            Some(GameState::Won(player)) => {
+                writeln!(f, "result won {}", player.name())
+            }
+            Some(GameState::Draw(reason)) => writeln!(f, "result draw {}", reason),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn game_record_from_str(record_str: &str) -> Result<GameRecord, Box<dyn Error>> {
+    let (board_str, moves_str) = record_str.split_once("\n\n").unwrap_or((record_str, ""));
+    let mut moves = Vec::new();
+    let mut result = None;
+    for line in moves_str.lines().map(|line| line.trim()) {
+        if let Some(result_str) = line.strip_prefix("result ") {
+            result = Some(match result_str.split_once(' ') {
+                Some(("won", player)) => GameState::Won(player.parse()?),
+                Some(("draw", reason)) => GameState::Draw(reason.parse()?),
+                _ => return Err(format!("Invalid result: {}", result_str).into()),
+            });
+        } else if !line.is_empty() {
+            moves.push(line.parse()?);
+        }
+    }
+    let record = GameRecord {
+        start: position_from_str(board_str)?,
+        moves,
+        result,
+    };
+    record.check_moves()?;
+    Ok(record)
+}
+
+// neighbours of a cell on the star board, in axial hex coordinates
+pub static HEX_DIRECTIONS: [Coords; 6] = [
+    Coords { x: 1, y: -1 },
+    Coords { x: 1, y: 0 },
+    Coords { x: 0, y: 1 },
+    Coords { x: -1, y: 1 },
+    Coords { x: -1, y: 0 },
+    Coords { x: 0, y: -1 },
+];
+
+pub static ORTHOGONAL_DIRECTIONS: [Coords; 4] = [
+    Coords { x: -1, y: 0 },
+    Coords { x: 0, y: -1 },
+    Coords { x: 0, y: 1 },
+    Coords { x: 1, y: 0 },
+];
+
 pub static DIRECTIONS: [Coords; 8] = [
     Coords { x: -1, y: -1 },
     Coords { x: -1, y: 0 },
@@ -120,46 +1321,401 @@ pub static DIRECTIONS: [Coords; 8] = [
     Coords { x: 1, y: 1 },
 ];
 
-pub static PLAYER_WHITE_BASE: [Coords; 19] = [
-    Coords { x: 14, y: 11 },
-    Coords { x: 15, y: 11 },
-    Coords { x: 13, y: 12 },
-    Coords { x: 14, y: 12 },
-    Coords { x: 15, y: 12 },
-    Coords { x: 12, y: 13 },
-    Coords { x: 13, y: 13 },
-    Coords { x: 14, y: 13 },
-    Coords { x: 15, y: 13 },
-    Coords { x: 11, y: 14 },
-    Coords { x: 12, y: 14 },
-    Coords { x: 13, y: 14 },
-    Coords { x: 14, y: 14 },
-    Coords { x: 15, y: 14 },
-    Coords { x: 11, y: 15 },
-    Coords { x: 12, y: 15 },
-    Coords { x: 13, y: 15 },
-    Coords { x: 14, y: 15 },
-    Coords { x: 15, y: 15 },
-];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristics::{Heuristic, TableBuilder, WeightedHeuristic};
+    use crate::minimax::{alfa_beta, LogLevel};
 
-pub static PLAYER_BLACK_BASE: [Coords; 19] = [
-    Coords { x: 0, y: 0 },
-    Coords { x: 1, y: 0 },
-    Coords { x: 2, y: 0 },
-    Coords { x: 3, y: 0 },
-    Coords { x: 4, y: 0 },
-    Coords { x: 0, y: 1 },
-    Coords { x: 1, y: 1 },
-    Coords { x: 2, y: 1 },
-    Coords { x: 3, y: 1 },
-    Coords { x: 4, y: 1 },
-    Coords { x: 0, y: 2 },
-    Coords { x: 1, y: 2 },
-    Coords { x: 2, y: 2 },
-    Coords { x: 3, y: 2 },
-    Coords { x: 0, y: 3 },
-    Coords { x: 1, y: 3 },
-    Coords { x: 2, y: 3 },
-    Coords { x: 0, y: 4 },
-    Coords { x: 1, y: 4 },
-];
+    fn start(name: &str) -> Position {
+        let config = Rc::new(BoardConfig::named(name).unwrap());
+        let first_player = config.players[0];
+        Position::new(BoardState::start_position(config), first_player)
+    }
+
+    fn with_rules(name: &str, rules: &str) -> Position {
+        let mut config = BoardConfig::named(name).unwrap();
+        config.rules = rules.parse().unwrap();
+        let first_player = config.players[0];
+        Position::new(BoardState::start_position(Rc::new(config)), first_player)
+    }
+
+    // a whole game between two proximity heuristics
+    fn played_record(name: &str) -> GameRecord {
+        let start = start(name);
+        let config = start.board_state.config.clone();
+        let heuristic = WeightedHeuristic::proximity(&config, &TableBuilder::default(), 1.);
+        let mut seats = vec![heuristic; config.players.len()];
+        let mut heuristics: Vec<&mut dyn Heuristic> = seats
+            .iter_mut()
+            .map(|seat| seat as &mut dyn Heuristic)
+            .collect();
+        let mut record = GameRecord::new(start.clone());
+        let mut node = DecisionTreeNode::new(start);
+        alfa_beta(
+            &mut node,
+            1,
+            &mut heuristics,
+            Some(60),
+            &LogLevel::None,
+            &mut Some(&mut record),
+        )
+        .unwrap();
+        record
+    }
+
+    #[test]
+    fn records_parse_back() {
+        for name in ["8x8-10", "10x10-4p", "star-3p"] {
+            let record = played_record(name);
+            let parsed = game_record_from_str(&record.to_string()).unwrap();
+            assert_eq!(parsed.to_string(), record.to_string());
+            assert_eq!(parsed.positions().len(), record.moves.len() + 1);
+        }
+    }
+
+    #[test]
+    fn records_with_impossible_moves_are_rejected() {
+        let record = played_record("8x8-10");
+        let text = record.to_string();
+        let first_move = record.moves[0].to_string();
+        let board_and_moves = text.replacen(&format!("\n{}\n", first_move), "\n{}\n", 1);
+        for (bad_move, error) in [
+            ("b1-z40", "leaves the board"),
+            ("h8-g7", "has no piece on h8"),
+            ("a1-a5", "is not legal"),
+        ] {
+            let bad = board_and_moves.replacen("{}", bad_move, 1);
+            let parsed = game_record_from_str(&bad);
+            assert!(
+                parsed
+                    .as_ref()
+                    .is_err_and(|parsed| parsed.to_string().contains(error)),
+                "{}: {:?}",
+                bad_move,
+                parsed.map(|record| record.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn rules_parse_back() {
+        let text = "orthogonal,own-jumps,leave-target,no-reentry,blocked-win,blocked=loss,\
+                    vacate-home=20,repetition=3,no-progress=50,ply-cap=400";
+        let rules: Rules = text.parse().unwrap();
+        assert_eq!(rules.to_string(), text);
+        assert_eq!(rules.to_string().parse::<Rules>().unwrap(), rules);
+        assert_eq!("".parse::<Rules>().unwrap(), Rules::default());
+        assert_eq!(Rules::default().to_string(), "");
+        for bad in ["diagonal", "blocked=maybe", "ply-cap=x", "jumps=2"] {
+            assert!(bad.parse::<Rules>().is_err(), "{}", bad);
+        }
+    }
+
+    // every step of every move leads from one point to the next
+    fn segments(child: &DecisionTreeNode) -> Vec<(Coords, Coords)> {
+        let last_move = child.position.last_move.as_ref().unwrap();
+        let mut points = vec![last_move.from];
+        points.extend(&last_move.path);
+        points.windows(2).map(|pair| (pair[0], pair[1])).collect()
+    }
+
+    #[test]
+    fn movement_rules_limit_the_moves() {
+        let is_straight = |(from, to): (Coords, Coords)| from.x == to.x || from.y == to.y;
+        for (rules, diagonals) in [("", true), ("orthogonal", false)] {
+            let mut node = DecisionTreeNode::new(with_rules("8x8-10", rules));
+            node.generate_children();
+            let all_straight = node
+                .children
+                .iter()
+                .all(|child| segments(child).into_iter().all(is_straight));
+            assert_eq!(all_straight, !diagonals, "{}", rules);
+        }
+
+        // a white piece in front of black's camp can only be jumped over with any-jumps
+        let landing = Coords { x: 3, y: 3 };
+        for (rules, jumps_over_white) in [("", true), ("own-jumps", false)] {
+            let mut position = with_rules("8x8-10", rules);
+            position.board_state.coords_mut(Player::White)[0] = Coords { x: 2, y: 2 };
+            let mut node = DecisionTreeNode::new(position);
+            node.generate_children();
+            let reaches_landing = node.children.iter().any(|child| {
+                child
+                    .position
+                    .last_move
+                    .as_ref()
+                    .is_some_and(|last_move| last_move.path.contains(&landing))
+            });
+            assert_eq!(reaches_landing, jumps_over_white, "{}", rules);
+        }
+    }
+
+    #[test]
+    fn blocked_target_wins() {
+        for (rules, won) in [("", false), ("blocked-win", true)] {
+            let mut position = with_rules("8x8-10", rules);
+            let corner = position.board_state.config.target(Player::Black)[0];
+            let board_state = &mut position.board_state;
+            let white = board_state.coords_mut(Player::White);
+            let white_corner = white.iter().position(|coords| *coords == corner).unwrap();
+            white[white_corner] = Coords { x: 4, y: 3 };
+            assert!(!board_state.is_won(Player::Black), "{}", rules);
+            // white left at home fills the rest of black's target
+            board_state.coords_mut(Player::Black)[0] = corner;
+            assert_eq!(board_state.is_won(Player::Black), won, "{}", rules);
+        }
+    }
+
+    #[test]
+    fn home_has_to_be_vacated() {
+        let mut position = with_rules("8x8-10", "vacate-home=1");
+        // one black piece is out, so there are moves within the home camp too
+        position.board_state.coords_mut(Player::Black)[0] = Coords { x: 4, y: 4 };
+        let home = position.board_state.config.base(Player::Black).to_vec();
+        let leaves_home = |child: &DecisionTreeNode| {
+            let last_move = child.position.last_move.as_ref().unwrap();
+            home.contains(&last_move.from) && !home.contains(&last_move.to)
+        };
+        for (ply, vacate) in [(0, false), (2, true)] {
+            position.ply = ply;
+            assert_eq!(
+                position
+                    .board_state
+                    .must_vacate_home(Player::Black, ply / 2),
+                vacate
+            );
+            let mut node = DecisionTreeNode::new(position.clone());
+            node.generate_children();
+            assert_eq!(node.children.iter().all(leaves_home), vacate, "ply {}", ply);
+        }
+
+        // a player with every piece out of the home camp moves freely
+        let board_state = &mut position.board_state;
+        for (coords, i) in board_state.coords_mut(Player::Black).iter_mut().zip(0..) {
+            *coords = Coords {
+                x: i % 8,
+                y: 4 + i / 8,
+            };
+        }
+        assert!(!board_state.must_vacate_home(Player::Black, 5));
+    }
+
+    // the reasons the tracker gives for the positions after each of `plies` passes
+    fn draws_after_passes(rules: &str, plies: usize) -> Vec<Option<DrawReason>> {
+        let mut position = with_rules("8x8-10", rules);
+        let mut tracker = DrawTracker::new(&position);
+        (0..plies)
+            .map(|_| {
+                position = position.play(None);
+                tracker.update(&position)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draws_are_detected() {
+        assert_eq!(draws_after_passes("", 6), vec![None; 6]);
+        assert_eq!(
+            draws_after_passes("repetition=2", 2),
+            vec![None, Some(DrawReason::Repetition)]
+        );
+        assert_eq!(
+            draws_after_passes("repetition=3", 4),
+            vec![None, None, None, Some(DrawReason::Repetition)]
+        );
+        assert_eq!(
+            draws_after_passes("no-progress=3", 3),
+            vec![None, None, Some(DrawReason::NoProgress)]
+        );
+        assert_eq!(
+            draws_after_passes("ply-cap=2", 2),
+            vec![None, Some(DrawReason::PlyCap)]
+        );
+
+        // a move bringing a piece closer to its target starts the no-progress count again
+        let start = with_rules("8x8-10", "no-progress=2");
+        let mut tracker = DrawTracker::new(&start);
+        let mut node = DecisionTreeNode::new(start.clone());
+        node.generate_children();
+        let forward = node
+            .children
+            .into_iter()
+            .map(|child| child.position)
+            .find(|position| {
+                position.board_state.total_distance_to_targets()
+                    < start.board_state.total_distance_to_targets()
+            })
+            .unwrap();
+        assert_eq!(tracker.update(&start.play(None)), None);
+        assert_eq!(tracker.update(&forward), None);
+        assert_eq!(tracker.update(&forward.play(None)), None);
+        assert_eq!(
+            tracker.update(&forward.play(None).play(None)),
+            Some(DrawReason::NoProgress)
+        );
+    }
+
+    // the board text reads back into the same board, pieces and player to move
+    fn assert_parses_back(position: &Position) {
+        let text = position_to_string(position);
+        let parsed = position_from_str(&text).unwrap();
+        assert_eq!(
+            parsed.board_state.config, position.board_state.config,
+            "{}",
+            text
+        );
+        assert_eq!(
+            parsed.board_state.sorted_pieces(),
+            position.board_state.sorted_pieces(),
+            "{}",
+            text
+        );
+        assert_eq!(parsed.to_move, position.to_move, "{}", text);
+        assert_eq!(position_to_string(&parsed), text);
+    }
+
+    #[test]
+    fn four_player_boards_parse_back() {
+        for name in ["10x10-4p", "16x16-4p"] {
+            let mut position = start(name);
+            let config = position.board_state.config.clone();
+            assert_eq!(config.players.len(), 4);
+            // no two camps share a square
+            let camps: Vec<Coords> = config
+                .players
+                .iter()
+                .flat_map(|player| config.base(*player).to_vec())
+                .collect();
+            let squares: std::collections::HashSet<Coords> = camps.iter().copied().collect();
+            assert_eq!(squares.len(), camps.len());
+            assert_parses_back(&position);
+            position.to_move = config.players[2];
+            assert_parses_back(&position);
+        }
+    }
+
+    #[test]
+    fn star_boards_parse_back() {
+        for (name, player_count) in [("star", 2), ("star-3p", 3), ("star-4p", 4), ("star-6p", 6)] {
+            let position = start(name);
+            let config = position.board_state.config.clone();
+            assert_eq!(config.players.len(), player_count, "{}", name);
+            assert_eq!(config.directions().len(), 6);
+            for player in &config.players {
+                assert_eq!(position.board_state.coords(*player).len(), 10, "{}", name);
+            }
+            assert_parses_back(&position);
+        }
+    }
+
+    #[test]
+    fn handicaps_and_custom_camps_parse_back() {
+        // the handicap takes the pieces deepest in the camp first and always leaves one
+        let mut position = start("8x8-10");
+        let corner = position.board_state.config.base(Player::White)[0];
+        position.board_state.remove_pieces(Player::White, 3);
+        let white = position.board_state.coords(Player::White);
+        assert_eq!(white.len(), 7);
+        assert!(!white.contains(&corner));
+        assert_parses_back(&position);
+        position.board_state.remove_pieces(Player::White, 20);
+        assert_eq!(position.board_state.coords(Player::White).len(), 1);
+        assert_parses_back(&position);
+
+        // a smaller camp and a target of its own need extra lines
+        let mut config = BoardConfig::new(8, 8, &[3, 2, 1], 2);
+        config.targets[Player::Black.index()] = vec![Coords { x: 3, y: 7 }, Coords { x: 4, y: 7 }];
+        config.rules = "ply-cap=100".parse().unwrap();
+        let position = Position::new(BoardState::start_position(Rc::new(config)), Player::White);
+        let text = position_to_string(&position);
+        for line in [
+            "turn white",
+            "camp 3,2,1",
+            "rules ply-cap=100",
+            "target black d8,e8",
+        ] {
+            assert!(text.contains(line), "{}", text);
+        }
+        assert_parses_back(&position);
+    }
+
+    #[test]
+    fn boards_of_any_size() {
+        let config = BoardConfig::new(10, 6, &[3, 2, 1], 2);
+        let black = config.base(Player::Black);
+        let white = config.base(Player::White);
+        assert_eq!(black.len(), 6);
+        assert_eq!(black[0], Coords { x: 0, y: 0 });
+        for (black, white) in black.iter().zip(white) {
+            assert_eq!((white.x, white.y), (9 - black.x, 5 - black.y));
+        }
+        assert_eq!(config.target(Player::Black), white);
+        assert_eq!(config.target(Player::White), black);
+
+        assert_eq!(BoardConfig::standard().base(Player::Black).len(), 19);
+        for name in BoardConfig::VARIANTS {
+            let position = start(name);
+            let config = &position.board_state.config;
+            for player in &config.players {
+                assert!(config
+                    .base(*player)
+                    .iter()
+                    .all(|coords| config.is_in_board(*coords)));
+                assert!(!position.board_state.is_won(*player), "{}", name);
+            }
+        }
+        assert!(BoardConfig::named("9x9").is_none());
+    }
+
+    #[test]
+    fn positions_follow_the_turn_order() {
+        let start = start("10x10-4p");
+        assert_eq!(start.to_move, Player::Black);
+        let passed = start.play(None);
+        assert_eq!(
+            (passed.to_move, passed.moved(), passed.ply),
+            (Player::Red, Player::Black, 1)
+        );
+        assert_eq!(
+            passed.board_state.sorted_pieces(),
+            start.board_state.sorted_pieces()
+        );
+        assert!(passed.last_move.is_none());
+
+        let step: Move = "d1-e2".parse().unwrap();
+        let played = passed
+            .play(None)
+            .play(None)
+            .play(None)
+            .play(Some(step.clone()));
+        assert_eq!(
+            (played.to_move, played.moved(), played.ply),
+            (Player::Red, Player::Black, 5)
+        );
+        assert_eq!(played.board_state.player_at(step.to), Some(Player::Black));
+        assert!(played.board_state.is_empty(step.from));
+        assert_eq!(played.last_move.unwrap().to, step.to);
+        // the position it was played from stays as it was
+        assert_eq!(start.board_state.player_at(step.from), Some(Player::Black));
+    }
+
+    #[test]
+    fn turn_headers_name_the_first_player() {
+        let board = board_state_to_string(&start("10x10-4p").board_state);
+        assert_eq!(position_from_str(&board).unwrap().to_move, Player::Black);
+        let position = position_from_str(&format!("turn green\n{}", board)).unwrap();
+        assert_eq!(position.to_move, Player::Green);
+        assert!(position_to_string(&position).starts_with("turn green\n"));
+        for (header, error) in [
+            ("turn blue", "does not take part"),
+            ("turn grey", "Unknown player"),
+        ] {
+            let parsed = position_from_str(&format!("{}\n{}", header, board));
+            assert!(parsed.is_err_and(|error_message| error_message.to_string().contains(error)));
+        }
+        for player in Player::ALL {
+            assert_eq!(player.name().parse::<Player>().unwrap(), player);
+        }
+    }
+}
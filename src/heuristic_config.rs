@@ -0,0 +1,488 @@
+use crate::features;
+use crate::halma::{BoardConfig, Player};
+use crate::heuristics::{ProximityTables, TableBuilder, WeightedHeuristic};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fs,
+    path::Path,
+};
+
+// a heuristic described in a TOML file, either one of the built-in presets:
+//
+//     type = "complex"
+//     params = { single_power = 3.0 }
+//
+// or a list of weighted features:
+//
+//     [[features]]
+//     feature = "progress"
+//     weight = 0.9
+//
+// both may add a `[table]` section with the `TableBuilder` fields and a `seed`
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct HeuristicConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub params: HashMap<String, f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<FeatureConfig>,
+    #[serde(default)]
+    pub table: TableConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FeatureConfig {
+    pub feature: String,
+    pub weight: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct TableConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metric: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_bonus: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub home_bonus: Option<f32>,
+    // whole tables by player name, one list per row, replacing the generated ones
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, Vec<Vec<f32>>>,
+}
+
+impl TableConfig {
+    pub fn builder(&self) -> Result<TableBuilder, Box<dyn Error>> {
+        let mut builder = TableBuilder::default();
+        if let Some(metric) = &self.metric {
+            builder.metric = metric.parse()?;
+        }
+        if let Some(origin) = &self.origin {
+            builder.origin = origin.parse()?;
+        }
+        builder.offset = self.offset;
+        builder.scale = self.scale;
+        builder.target_bonus = self.target_bonus.unwrap_or(0.);
+        builder.home_bonus = self.home_bonus.unwrap_or(0.);
+        Ok(builder)
+    }
+
+    // puts the explicitly given tables in place of the generated ones
+    pub fn apply_values(
+        &self,
+        tables: &mut ProximityTables,
+        config: &BoardConfig,
+    ) -> Result<(), Box<dyn Error>> {
+        for (player, values) in &self.values {
+            let rows_match = values.len() == config.height as usize
+                && values.iter().all(|row| row.len() == config.width as usize);
+            if !rows_match {
+                return Err(format!(
+                    "The table of {} has to have {} rows of {} values",
+                    player, config.height, config.width
+                )
+                .into());
+            }
+            tables.set_table(player.parse::<Player>()?, values.clone());
+        }
+        Ok(())
+    }
+}
+
+// a named heuristic constructor and the parameters it takes, with their default values
+pub struct RegisteredHeuristic {
+    pub name: &'static str,
+    pub params: &'static [(&'static str, f32)],
+    // gets every parameter of `params`, with the defaults filled in
+    pub build: fn(&BoardConfig, &TableBuilder, &HashMap<String, f32>) -> WeightedHeuristic,
+}
+
+// the heuristics which can be selected by name; new ones only need an entry here
+pub const REGISTRY: [RegisteredHeuristic; 5] = [
+    RegisteredHeuristic {
+        name: "random",
+        params: &[],
+        build: |config, tables, _| WeightedHeuristic::random(config, tables),
+    },
+    RegisteredHeuristic {
+        name: "proximity",
+        params: &[("power", 1.08)],
+        build: |config, tables, params| {
+            WeightedHeuristic::proximity(config, tables, params["power"])
+        },
+    },
+    RegisteredHeuristic {
+        name: "leading",
+        params: &[("single_power", 2.5), ("multi_power", 0.9)],
+        build: |config, tables, params| {
+            WeightedHeuristic::leading(
+                config,
+                tables,
+                params["single_power"],
+                params["multi_power"],
+            )
+        },
+    },
+    RegisteredHeuristic {
+        name: "discourage",
+        params: &[("other_power", 1.1), ("discourage_power", 1.0)],
+        build: |config, tables, params| {
+            WeightedHeuristic::discourage(
+                config,
+                tables,
+                params["other_power"],
+                params["discourage_power"],
+            )
+        },
+    },
+    RegisteredHeuristic {
+        name: "complex",
+        params: &[
+            ("single_power", 2.5),
+            ("multi_power", 0.9),
+            ("discourage_power", 1.0),
+            ("endgame_power", 1.0),
+        ],
+        build: |config, tables, params| {
+            WeightedHeuristic::complex(
+                config,
+                tables,
+                params["single_power"],
+                params["multi_power"],
+                params["discourage_power"],
+                params["endgame_power"],
+            )
+        },
+    },
+];
+
+pub fn registered(name: &str) -> Result<&'static RegisteredHeuristic, Box<dyn Error>> {
+    REGISTRY
+        .iter()
+        .find(|registered| registered.name == name)
+        .ok_or(format!("Unknown heuristic type: {}", name).into())
+}
+
+fn preset(
+    name: &str,
+    params: &HashMap<String, f32>,
+    config: &BoardConfig,
+    tables: &TableBuilder,
+) -> Result<WeightedHeuristic, Box<dyn Error>> {
+    let registered = registered(name)?;
+    if let Some(unknown) = params
+        .keys()
+        .find(|key| !registered.params.iter().any(|(param, _)| param == key))
+    {
+        return Err(format!("Unknown parameter of {}: {}", name, unknown).into());
+    }
+    let mut all_params = params.clone();
+    for (param, default) in registered.params {
+        all_params.entry(param.to_string()).or_insert(*default);
+    }
+    Ok((registered.build)(config, tables, &all_params))
+}
+
+impl HeuristicConfig {
+    pub fn build(&self, config: &BoardConfig) -> Result<WeightedHeuristic, Box<dyn Error>> {
+        let tables = self.table.builder()?;
+        let mut heuristic = match (&self.preset, self.features.is_empty()) {
+            (Some(preset_name), true) => preset(preset_name, &self.params, config, &tables)?,
+            (None, false) => {
+                if !self.params.is_empty() {
+                    return Err("Parameters only apply to a heuristic type".into());
+                }
+                let mut weighted = Vec::new();
+                for feature_config in &self.features {
+                    let feature = features::named(&feature_config.feature)
+                        .ok_or(format!("Unknown feature: {}", feature_config.feature))?;
+                    weighted.push((feature_config.weight, feature));
+                }
+                WeightedHeuristic {
+                    name: "Weighted features".to_owned(),
+                    tables: tables.build(config),
+                    features: weighted,
+                }
+            }
+            _ => return Err("Expected either a heuristic type or a list of features".into()),
+        };
+        self.table.apply_values(&mut heuristic.tables, config)?;
+        if let Some(name) = &self.name {
+            heuristic.name = name.clone();
+        }
+        if let Some(seed) = self.seed {
+            heuristic.seed(seed);
+        }
+        Ok(heuristic)
+    }
+}
+
+// `value` as TOML; serializing goes through f64, so every float is written the way the f32
+// prints instead of with the digits of its binary expansion
+pub fn to_tidy_toml<T: Serialize>(value: &T) -> Result<String, Box<dyn Error>> {
+    fn tidy(value: &mut toml::Value) {
+        match value {
+            toml::Value::Float(float) => *float = (*float as f32).to_string().parse().unwrap(),
+            toml::Value::Array(array) => array.iter_mut().for_each(tidy),
+            toml::Value::Table(table) => table.iter_mut().for_each(|(_, value)| tidy(value)),
+            _ => (),
+        }
+    }
+    let mut value = toml::Value::try_from(value)?;
+    tidy(&mut value);
+    Ok(toml::to_string(&value)?)
+}
+
+// parameter values by name
+pub type Params = BTreeMap<String, f32>;
+
+// splits `leading:single_power=3:multi_power=1` into the name and the parameters
+pub fn parse_spec(spec: &str) -> Result<(&str, Params), Box<dyn Error>> {
+    let mut parts = spec.split(':');
+    let name = parts.next().unwrap_or_default();
+    let mut params = BTreeMap::new();
+    for param in parts {
+        let (key, value) = param
+            .split_once('=')
+            .ok_or(format!("Expected <parameter>=<value>, got: {}", param))?;
+        let value: f32 = value
+            .parse()
+            .map_err(|error| format!("Could not parse {}: {}", param, error))?;
+        params.insert(key.to_owned(), value);
+    }
+    Ok((name, params))
+}
+
+// a registered heuristic name or the path of a TOML file, optionally followed by parameters
+// overriding the defaults, e.g. `leading:single_power=3:multi_power=1`
+pub fn load_heuristic(
+    spec: &str,
+    config: &BoardConfig,
+) -> Result<WeightedHeuristic, Box<dyn Error>> {
+    let (name_or_file, params) = parse_spec(spec)?;
+    let path = Path::new(name_or_file);
+    let mut heuristic_config = if path.is_file() {
+        let mut heuristic_config: HeuristicConfig = toml::from_str(&fs::read_to_string(path)?)?;
+        if heuristic_config.name.is_none() {
+            heuristic_config.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned());
+        }
+        heuristic_config
+    } else {
+        HeuristicConfig {
+            preset: Some(name_or_file.to_owned()),
+            ..HeuristicConfig::default()
+        }
+    };
+    heuristic_config.params.extend(params);
+    heuristic_config.build(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::halma::{BoardState, Position};
+    use crate::heuristics::Heuristic;
+    use crate::random_position::random_playout;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::rc::Rc;
+
+    #[test]
+    fn floats_are_written_as_f32() {
+        let feature = FeatureConfig {
+            feature: "progress".to_owned(),
+            weight: 0.9,
+        };
+        assert_eq!(
+            to_tidy_toml(&feature).unwrap(),
+            "feature = \"progress\"\nweight = 0.9\n"
+        );
+    }
+
+    #[test]
+    fn specs() {
+        let (name, params) = parse_spec("leading:single_power=3:multi_power=0.5").unwrap();
+        assert_eq!(name, "leading");
+        assert_eq!(
+            params.into_iter().collect::<Vec<_>>(),
+            [
+                ("multi_power".to_owned(), 0.5),
+                ("single_power".to_owned(), 3.)
+            ]
+        );
+        assert!(parse_spec("heuristics/noisy.toml").unwrap().1.is_empty());
+        assert!(parse_spec("leading:single_power").is_err());
+        assert!(parse_spec("leading:single_power=high").is_err());
+    }
+
+    // the file spelling the preset out has to play the same as the preset itself
+    #[test]
+    fn complex_features_file_matches_the_preset() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for name in BoardConfig::VARIANTS {
+            let config = Rc::new(BoardConfig::named(name).unwrap());
+            let mut preset = load_heuristic("complex", &config).unwrap();
+            let mut file = load_heuristic("heuristics/complex-features.toml", &config).unwrap();
+            let start = Position::new(
+                BoardState::start_position(config.clone()),
+                config.players[0],
+            );
+            // random positions early in the game and an endgame, the first player alone on the
+            // board with two pieces left at home
+            let player = config.players[0];
+            let mut endgame = BoardState::start_position(config.clone());
+            for other in config.opponents(player) {
+                endgame.coords_mut(other).clear();
+            }
+            // the two squares furthest from home stay empty, so the pieces have to get past
+            // the filled ones
+            let home = config.base(player)[0];
+            let mut target = config.target(player).to_vec();
+            target.sort_by_key(|square| config.distance(home, *square));
+            let mut pieces = target[..target.len() - 2].to_vec();
+            pieces.extend_from_slice(&config.base(player)[..2]);
+            *endgame.coords_mut(player) = pieces;
+            assert!(
+                features::named("assignment").unwrap().value(
+                    &endgame,
+                    player,
+                    &ProximityTables::new(&config)
+                ) < 0.
+            );
+            let positions = [0, 30, 150]
+                .map(|plies| random_playout(&start, plies, &mut rng).board_state)
+                .into_iter()
+                .chain([endgame]);
+            for (idx, board_state) in positions.enumerate() {
+                for player in &config.players {
+                    let expected = preset.evaluate(&board_state, *player, 0);
+                    let actual = file.evaluate(&board_state, *player, 0);
+                    assert!(
+                        (expected - actual).abs() < 1e-3,
+                        "{} position {}: {} against {}",
+                        name,
+                        idx,
+                        expected,
+                        actual
+                    );
+                }
+            }
+        }
+    }
+
+    fn build(toml_text: &str) -> Result<WeightedHeuristic, Box<dyn Error>> {
+        let heuristic_config: HeuristicConfig = toml::from_str(toml_text)?;
+        heuristic_config.build(&BoardConfig::named("8x8-10").unwrap())
+    }
+
+    #[test]
+    fn heuristic_files() {
+        let heuristic = build(
+            "name = \"mine\"\n[[features]]\nfeature = \"progress\"\nweight = 2.0\n\
+             [table]\nmetric = \"moves\"\nhome_bonus = -1.0",
+        )
+        .unwrap();
+        assert_eq!(heuristic.name, "mine");
+        assert_eq!(heuristic.features.len(), 1);
+        assert_eq!(heuristic.features[0].0, 2.);
+        let preset = build("type = \"leading\"\nparams = { single_power = 3.0 }").unwrap();
+        assert_eq!(preset.features.last().unwrap().0, 3.);
+
+        for (toml_text, error) in [
+            ("type = \"leading\"\ncolour = 1", "unknown field"),
+            ("", "Expected either"),
+            (
+                "type = \"leading\"\n[[features]]\nfeature = \"progress\"\nweight = 1.0",
+                "Expected either",
+            ),
+            (
+                "params = { power = 1.0 }\n[[features]]\nfeature = \"progress\"\nweight = 1.0",
+                "only apply",
+            ),
+            (
+                "[[features]]\nfeature = \"speed\"\nweight = 1.0",
+                "Unknown feature",
+            ),
+            (
+                "type = \"random\"\n[table]\nmetric = \"taxicab\"",
+                "Unknown distance metric",
+            ),
+            (
+                "type = \"random\"\n[table.values]\nblack = [[1.0, 2.0]]",
+                "has to have 8 rows",
+            ),
+        ] {
+            let built = build(toml_text);
+            assert!(
+                built
+                    .as_ref()
+                    .is_err_and(|built| built.to_string().contains(error)),
+                "{}: {:?}",
+                toml_text,
+                built.map(|heuristic| heuristic.name)
+            );
+        }
+
+        // the files shipped with the engine load, named after the file unless they set a name
+        let config = BoardConfig::named("8x8-10").unwrap();
+        for entry in fs::read_dir("heuristics").unwrap() {
+            let path = entry.unwrap().path();
+            let heuristic = load_heuristic(path.to_str().unwrap(), &config).unwrap();
+            let file: HeuristicConfig =
+                toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            if file.name.is_none() {
+                assert_eq!(heuristic.name, path.file_stem().unwrap().to_str().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn every_registered_heuristic_loads() {
+        let config = BoardConfig::named("8x8-10").unwrap();
+        for registered in REGISTRY {
+            assert!(
+                load_heuristic(registered.name, &config).is_ok(),
+                "{}",
+                registered.name
+            );
+            for (param, _) in registered.params {
+                let spec = format!("{}:{}=2", registered.name, param);
+                assert!(load_heuristic(&spec, &config).is_ok(), "{}", spec);
+            }
+        }
+        // parameters replace the defaults
+        let leading = load_heuristic("leading:single_power=4", &config).unwrap();
+        let weights: Vec<f32> = leading.features.iter().map(|(weight, _)| *weight).collect();
+        assert_eq!(weights, [0.9, 0.9, 0.9, 4.]);
+
+        for (spec, error) in [
+            ("fastest", "Unknown heuristic type"),
+            ("leading:power=2", "Unknown parameter of leading: power"),
+        ] {
+            let loaded = load_heuristic(spec, &config);
+            assert!(
+                loaded.is_err_and(|loaded| loaded.to_string().contains(error)),
+                "{}",
+                spec
+            );
+        }
+    }
+}
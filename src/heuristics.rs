@@ -1,5 +1,9 @@
-use crate::halma::{BoardState, Coords, Player, DIRECTIONS, PLAYER_BLACK_BASE, PLAYER_WHITE_BASE};
-use rand::prelude::*;
+use crate::features::{
+    Assignment, Blocking, Edges, Feature, HomeOccupancy, LeadingPiece, Noise, Progress,
+};
+use crate::halma::{BoardConfig, BoardState, Coords, Player, Topology};
+
+use std::{collections::VecDeque, error::Error, str::FromStr};
 
 pub trait Heuristic {
     fn evaluate(
@@ -11,430 +15,532 @@ pub trait Heuristic {
     fn name(&self) -> String;
 }
 
+// per-square values rewarding pieces for getting closer to the target camp
 #[derive(Clone)]
-pub struct HeuristicRandom {
-    pub rng: ThreadRng,
+pub struct ProximityTables {
+    // indexed by `Player::index`
+    tables: Vec<Vec<Vec<f32>>>,
 }
 
-#[derive(Clone)]
-pub struct HeuristicProximity {
-    pub power: f32,
-    pub rng: ThreadRng,
-}
+impl ProximityTables {
+    // manhattan distance from the own corner, the way the original hand-made tables were built
+    pub fn new(config: &BoardConfig) -> Self {
+        TableBuilder::default().build(config)
+    }
 
-#[derive(Clone)]
-pub struct HeuristicProximityWithSingle {
-    pub single_power: f32,
-    pub multi_power: f32,
-    pub rng: ThreadRng,
-}
+    #[inline]
+    pub fn value(&self, player: Player, coords: Coords) -> f32 {
+        self.tables[player.index()][coords.y as usize][coords.x as usize]
+    }
 
-#[derive(Clone)]
-pub struct HeuristicDiscourageStart {
-    pub other_power: f32,
-    pub discourage_power: f32,
-}
+    pub fn table(&self, player: Player) -> &Vec<Vec<f32>> {
+        &self.tables[player.index()]
+    }
 
-#[derive(Clone)]
-pub struct HeuristicComplex {
-    pub single_power: f32,
-    pub multi_power: f32,
-    pub discourage_power: f32,
-}
+    pub fn set_table(&mut self, player: Player, table: Vec<Vec<f32>>) {
+        self.tables[player.index()] = table;
+    }
 
-impl Heuristic for HeuristicRandom {
-    fn evaluate(&mut self, board_state: &BoardState, evaluating_player: Player, _: u32) -> f32 {
-        let mut score: f32 = self.rng.gen_range(-100.0..100.0);
-        match evaluating_player {
-            Player::Black => {
-                for coord in board_state.black_coords {
-                    if PLAYER_BLACK_BASE.contains(&coord) {
-                        score -= 2.;
-                    }
-                }
-            }
-            Player::White => {
-                for coord in board_state.white_coords {
-                    if PLAYER_WHITE_BASE.contains(&coord) {
-                        score -= 2.;
-                    }
+    // one row of the board per line, laid out like the text board format
+    pub fn table_to_string(&self, config: &BoardConfig, player: Player) -> String {
+        // star cells are two text columns apart
+        let column_width = match config.topology {
+            Topology::Square => 6,
+            Topology::Star { .. } => 3,
+        };
+        let mut table = String::new();
+        for y in 0..config.height {
+            let mut row = String::new();
+            for column in 0..config.text_width() {
+                if let Some(coords) = config.text_cell(column, y) {
+                    row += &" ".repeat((column * column_width).saturating_sub(row.len()));
+                    row += &format!("{:6.2}", self.value(player, coords));
                 }
             }
+            table += &row;
+            table += "\n";
         }
-        return score.clamp(-100., 100.);
+        table
     }
-    fn name(&self) -> String {
-        return "Random".to_owned();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DistanceMetric {
+    Manhattan,
+    Chebyshev,
+    Euclidean,
+    // least number of moves on an empty board, where a move is a step or a single jump
+    Moves,
+}
+
+impl FromStr for DistanceMetric {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "manhattan" => Ok(DistanceMetric::Manhattan),
+            "chebyshev" => Ok(DistanceMetric::Chebyshev),
+            "euclidean" => Ok(DistanceMetric::Euclidean),
+            "moves" => Ok(DistanceMetric::Moves),
+            _ => Err(format!("Unknown distance metric: {}", s).into()),
+        }
     }
 }
 
-impl Heuristic for HeuristicProximity {
-    fn evaluate(
-        &mut self,
-        board_state: &BoardState,
-        evaluating_player: Player,
-        round_number: u32,
-    ) -> f32 {
-        // let mut score: f32 = if round_number > 200 {
-        //     self.rng.gen_range(-0.1..0.1)
-        // } else {
-        //     0.
-        // };
-        let mut score = 0.;
-        match evaluating_player {
-            Player::Black => {
-                for coord in board_state.black_coords {
-                    score += PLAYER_BLACK_HEURISTIC_PROXIMITY[coord.y as usize][coord.x as usize];
-                    score += dont_block_other_player_in_base(
-                        &coord,
-                        &PLAYER_WHITE_BASE,
-                        &board_state.white_coords,
-                    );
-                    score += discourage_edges(&coord);
-                }
-            }
-            Player::White => {
-                for coord in board_state.white_coords {
-                    score += PLAYER_WHITE_HEURISTIC_PROXIMITY[coord.y as usize][coord.x as usize];
-                    score += dont_block_other_player_in_base(
-                        &coord,
-                        &PLAYER_BLACK_BASE,
-                        &board_state.black_coords,
-                    );
-                    score += discourage_edges(&coord);
-                }
-            }
+// what the distance of a square is measured from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TableOrigin {
+    // the corner of the own camp, the further away the better
+    HomeCorner,
+    // the nearest square of the target camp, the closer the better
+    TargetCamp,
+}
+
+impl FromStr for TableOrigin {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "corner" => Ok(TableOrigin::HomeCorner),
+            "target" => Ok(TableOrigin::TargetCamp),
+            _ => Err(format!("Unknown table origin: {}", s).into()),
         }
-        return (score * self.power).clamp(-100.0, 100.0);
     }
+}
 
-    fn name(&self) -> String {
-        return "Proximity table".to_owned();
+// builds a proximity table for every player: the distance of each square from `origin`
+// turned into progress, shifted by `offset`, divided by `scale`, plus the camp bonuses
+#[derive(Clone, Debug)]
+pub struct TableBuilder {
+    pub metric: DistanceMetric,
+    pub origin: TableOrigin,
+    // `None` centers the values around zero
+    pub offset: Option<f32>,
+    // `None` spreads the whole way across the board over ten points
+    pub scale: Option<f32>,
+    pub target_bonus: f32,
+    // negative values discourage staying at home
+    pub home_bonus: f32,
+}
+
+impl Default for TableBuilder {
+    fn default() -> Self {
+        TableBuilder {
+            metric: DistanceMetric::Manhattan,
+            origin: TableOrigin::HomeCorner,
+            offset: None,
+            scale: None,
+            target_bonus: 0.,
+            home_bonus: 0.,
+        }
     }
 }
 
-impl Heuristic for HeuristicProximityWithSingle {
-    fn evaluate(
-        &mut self,
-        board_state: &BoardState,
-        evaluating_player: Player,
-        round_number: u32,
-    ) -> f32 {
-        // let mut score: f32 = if round_number > 200 {
-        //     self.rng.gen_range(-0.1..0.1)
-        // } else {
-        //     0.
-        // };
-        let mut score = 0.;
-        let mut max: f32 = f32::NEG_INFINITY;
-        match evaluating_player {
-            Player::Black => {
-                for coord in board_state.black_coords {
-                    let tile_ev =
-                        PLAYER_BLACK_HEURISTIC_PROXIMITY[coord.y as usize][coord.x as usize];
-                    score += tile_ev;
-                    if tile_ev > max {
-                        max = tile_ev;
-                    }
-                    score += dont_block_other_player_in_base(
-                        &coord,
-                        &PLAYER_WHITE_BASE,
-                        &board_state.white_coords,
-                    );
-                    score += discourage_edges(&coord);
-                }
-            }
-            Player::White => {
-                for coord in board_state.white_coords {
-                    let tile_ev =
-                        PLAYER_WHITE_HEURISTIC_PROXIMITY[coord.y as usize][coord.x as usize];
-                    score += tile_ev;
-                    if tile_ev > max {
-                        max = tile_ev;
-                    }
-                    score += dont_block_other_player_in_base(
-                        &coord,
-                        &PLAYER_BLACK_BASE,
-                        &board_state.black_coords,
-                    );
-                    score += discourage_edges(&coord);
-                }
+impl TableBuilder {
+    // the star has no straight columns, so manhattan and chebyshev distance both become
+    // the hex distance there and euclidean distance is taken between the drawn cell centers
+    fn distance(&self, config: &BoardConfig, from: Coords, to: Coords) -> f32 {
+        let dx = (to.x - from.x) as f32;
+        let dy = (to.y - from.y) as f32;
+        match (self.metric, config.topology) {
+            (DistanceMetric::Manhattan, Topology::Square) => dx.abs() + dy.abs(),
+            (DistanceMetric::Chebyshev, Topology::Square) => dx.abs().max(dy.abs()),
+            (DistanceMetric::Euclidean, Topology::Square) => (dx * dx + dy * dy).sqrt(),
+            (DistanceMetric::Euclidean, Topology::Star { .. }) => {
+                let (x, y) = (dx + dy / 2., dy * 0.866);
+                (x * x + y * y).sqrt()
             }
+            _ => config.distance(from, to) as f32,
         }
-        score = score * self.multi_power + max * self.single_power;
-        return score.clamp(-100.0, 100.0);
-    }
-    fn name(&self) -> String {
-        return "Proximity table with leading piece".to_owned();
     }
-}
 
-impl Heuristic for HeuristicDiscourageStart {
-    fn evaluate(
-        &mut self,
-        board_state: &BoardState,
-        evaluating_player: Player,
-        round_number: u32,
-    ) -> f32 {
-        // let mut score: f32 = if round_number > 200 {
-        //     self.rng.gen_range(-0.1..0.1)
-        // } else {
-        //     0.
-        // };
-        let mut score = 0.;
-        match evaluating_player {
-            Player::Black => {
-                for coord in board_state.black_coords {
-                    score += PLAYER_BLACK_HEURISTIC_PROXIMITY[coord.y as usize][coord.x as usize];
-                    if PLAYER_BLACK_BASE.contains(&coord) {
-                        score -= self.discourage_power;
-                    }
+    // distance of every square to the nearest of `sources`, indexed by y and x
+    fn distances(&self, config: &BoardConfig, sources: &[Coords]) -> Vec<Vec<f32>> {
+        if self.metric == DistanceMetric::Moves {
+            return Self::move_distances(config, sources);
+        }
+        (0..config.height)
+            .map(|y| {
+                (0..config.width)
+                    .map(|x| {
+                        sources
+                            .iter()
+                            .map(|source| self.distance(config, *source, Coords { x, y }))
+                            .fold(f32::INFINITY, f32::min)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
 
-                    score += dont_block_other_player_in_base(
-                        &coord,
-                        &PLAYER_WHITE_BASE,
-                        &board_state.white_coords,
-                    );
-                    score += discourage_edges(&coord);
-                }
-            }
-            Player::White => {
-                for coord in board_state.white_coords {
-                    score += PLAYER_WHITE_HEURISTIC_PROXIMITY[coord.y as usize][coord.x as usize];
-                    if PLAYER_WHITE_BASE.contains(&coord) {
-                        score -= self.discourage_power;
+    // breadth first search where every square reaches its neighbours and the squares
+    // right behind them; squares off the board stay at zero
+    fn move_distances(config: &BoardConfig, sources: &[Coords]) -> Vec<Vec<f32>> {
+        let mut distances = vec![vec![0.; config.width as usize]; config.height as usize];
+        let mut visited = vec![vec![false; config.width as usize]; config.height as usize];
+        let mut queue = VecDeque::new();
+        for source in sources {
+            visited[source.y as usize][source.x as usize] = true;
+            queue.push_back(*source);
+        }
+        while let Some(square) = queue.pop_front() {
+            let distance = distances[square.y as usize][square.x as usize];
+            for direction in config.directions() {
+                for length in 1..=2 {
+                    let next = Coords {
+                        x: square.x + direction.x * length,
+                        y: square.y + direction.y * length,
+                    };
+                    if config.is_in_board(next) && !visited[next.y as usize][next.x as usize] {
+                        visited[next.y as usize][next.x as usize] = true;
+                        distances[next.y as usize][next.x as usize] = distance + 1.;
+                        queue.push_back(next);
                     }
-                    score += dont_block_other_player_in_base(
-                        &coord,
-                        &PLAYER_BLACK_BASE,
-                        &board_state.black_coords,
-                    );
-                    score += discourage_edges(&coord);
                 }
             }
         }
-        return (score * self.other_power).clamp(-100.0, 100.0);
+        distances
     }
 
-    fn name(&self) -> String {
-        return "Proximity table discourage start".to_owned();
+    pub fn build(&self, config: &BoardConfig) -> ProximityTables {
+        let tables = Player::ALL
+            .iter()
+            .map(|player| {
+                // players without a corner on this board never get evaluated
+                let corner = match config.base(*player).first() {
+                    Some(corner) => *corner,
+                    None => return Vec::new(),
+                };
+                let target = config.target(*player);
+                // measured with the same distances as the table, so that it is centred for
+                // every metric
+                let (distances, max_distance) = match self.origin {
+                    TableOrigin::HomeCorner => {
+                        let from_corner = self.distances(config, &[corner]);
+                        let max_distance = target
+                            .first()
+                            .map_or(0., |far| from_corner[far.y as usize][far.x as usize]);
+                        (from_corner, max_distance)
+                    }
+                    TableOrigin::TargetCamp => {
+                        let from_target = self.distances(config, target);
+                        let max_distance = from_target[corner.y as usize][corner.x as usize];
+                        (from_target, max_distance)
+                    }
+                };
+                let offset = self.offset.unwrap_or(max_distance / 2. - 0.5);
+                let scale = self.scale.unwrap_or(max_distance / 10.);
+                (0..config.height)
+                    .map(|y| {
+                        (0..config.width)
+                            .map(|x| {
+                                let coords = Coords { x, y };
+                                let distance = distances[y as usize][x as usize];
+                                let progress = match self.origin {
+                                    TableOrigin::HomeCorner => distance,
+                                    TableOrigin::TargetCamp => max_distance - distance,
+                                };
+                                let mut value = (progress - offset) / scale;
+                                if target.contains(&coords) {
+                                    value += self.target_bonus;
+                                }
+                                if config.base(*player).contains(&coords) {
+                                    value += self.home_bonus;
+                                }
+                                value
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        ProximityTables { tables }
     }
 }
 
-impl Heuristic for HeuristicComplex {
+// weighted sum of features, evaluated on the proximity tables of the heuristic
+#[derive(Clone)]
+pub struct WeightedHeuristic {
+    pub name: String,
+    pub tables: ProximityTables,
+    pub features: Vec<(f32, Box<dyn Feature>)>,
+}
+
+impl Heuristic for WeightedHeuristic {
     fn evaluate(
         &mut self,
         board_state: &BoardState,
         evaluating_player: Player,
         _round_number: u32,
     ) -> f32 {
-        // let mut score: f32 = if round_number > 200 {
-        //     self.rng.gen_range(-0.1..0.1)
-        // } else {
-        //     0.
-        // };
         let mut score = 0.;
-        let mut max: f32 = f32::NEG_INFINITY;
-        match evaluating_player {
-            Player::Black => {
-                for coord in board_state.black_coords {
-                    let mut tile_ev =
-                        PLAYER_BLACK_HEURISTIC_PROXIMITY[coord.y as usize][coord.x as usize];
-                    if PLAYER_BLACK_BASE.contains(&coord) {
-                        tile_ev -= self.discourage_power;
-                    }
-                    score += tile_ev;
-                    if tile_ev > max {
-                        max = tile_ev;
-                    }
-                    score += dont_block_other_player_in_base(
-                        &coord,
-                        &PLAYER_WHITE_BASE,
-                        &board_state.white_coords,
-                    );
-                    score += discourage_edges(&coord);
-                }
-            }
-            Player::White => {
-                for coord in board_state.white_coords {
-                    let mut tile_ev =
-                        PLAYER_WHITE_HEURISTIC_PROXIMITY[coord.y as usize][coord.x as usize];
-                    if PLAYER_WHITE_BASE.contains(&coord) {
-                        tile_ev -= self.discourage_power;
-                    }
-                    score += tile_ev;
-                    if tile_ev > max {
-                        max = tile_ev;
-                    }
-                    score += dont_block_other_player_in_base(
-                        &coord,
-                        &PLAYER_BLACK_BASE,
-                        &board_state.black_coords,
-                    );
-                    score += discourage_edges(&coord);
-                }
-            }
+        for (weight, feature) in self.features.iter_mut() {
+            score += *weight * feature.value(board_state, evaluating_player, &self.tables);
         }
-        return (score * self.multi_power + max * self.single_power).clamp(-100., 100.);
+        score.clamp(-100., 100.)
     }
 
     fn name(&self) -> String {
-        return "Complex".to_owned();
+        self.name.clone()
     }
 }
 
-#[inline]
-fn dont_block_other_player_in_base(
-    piece_coords: &Coords,
-    other_player_base: &[Coords; 19],
-    other_player_pieces: &[Coords; 19],
-) -> f32 {
-    let mut score = 0.;
-    for direction in DIRECTIONS {
-        let looking_at_coords = Coords {
-            x: piece_coords.x + direction.x,
-            y: piece_coords.y + direction.y,
-        };
-        if looking_at_coords.is_in_board() {
-            if other_player_pieces.contains(&looking_at_coords) {
-                if other_player_base.contains(&looking_at_coords) {
-                    score -= 1.;
-                }
-            }
+impl WeightedHeuristic {
+    // makes the random features repeat the same values in every run
+    pub fn seed(&mut self, seed: u64) {
+        for (_, feature) in self.features.iter_mut() {
+            feature.seed(seed);
         }
     }
-    return score;
 }
 
-#[inline]
-fn discourage_edges(piece_coords: &Coords) -> f32 {
-    if piece_coords.y == 0 || piece_coords.y == 15 {
-        return -0.1;
+// the original hand-written heuristics; a home camp penalty is built into the tables,
+// so that it also lowers the value of a leading piece which has not left home yet
+impl WeightedHeuristic {
+    pub fn random(config: &BoardConfig, tables: &TableBuilder) -> Self {
+        WeightedHeuristic {
+            name: "Random".to_owned(),
+            tables: tables.build(config),
+            features: vec![
+                (1., Box::new(Noise::default())),
+                (-2., Box::new(HomeOccupancy)),
+            ],
+        }
+    }
+
+    pub fn proximity(config: &BoardConfig, tables: &TableBuilder, power: f32) -> Self {
+        WeightedHeuristic {
+            name: "Proximity table".to_owned(),
+            tables: tables.build(config),
+            features: vec![
+                (power, Box::new(Progress)),
+                (power, Box::new(Blocking)),
+                (power, Box::new(Edges)),
+            ],
+        }
+    }
+
+    pub fn leading(
+        config: &BoardConfig,
+        tables: &TableBuilder,
+        single_power: f32,
+        multi_power: f32,
+    ) -> Self {
+        WeightedHeuristic {
+            name: "Proximity table with leading piece".to_owned(),
+            tables: tables.build(config),
+            features: vec![
+                (multi_power, Box::new(Progress)),
+                (multi_power, Box::new(Blocking)),
+                (multi_power, Box::new(Edges)),
+                (single_power, Box::new(LeadingPiece)),
+            ],
+        }
+    }
+
+    pub fn discourage(
+        config: &BoardConfig,
+        tables: &TableBuilder,
+        other_power: f32,
+        discourage_power: f32,
+    ) -> Self {
+        let builder = TableBuilder {
+            home_bonus: tables.home_bonus - discourage_power,
+            ..tables.clone()
+        };
+        WeightedHeuristic {
+            name: "Proximity table discourage start".to_owned(),
+            tables: builder.build(config),
+            features: vec![
+                (other_power, Box::new(Progress)),
+                (other_power, Box::new(Blocking)),
+                (other_power, Box::new(Edges)),
+            ],
+        }
+    }
+
+    pub fn complex(
+        config: &BoardConfig,
+        tables: &TableBuilder,
+        single_power: f32,
+        multi_power: f32,
+        discourage_power: f32,
+        endgame_power: f32,
+    ) -> Self {
+        let builder = TableBuilder {
+            home_bonus: tables.home_bonus - discourage_power,
+            ..tables.clone()
+        };
+        let mut features: Vec<(f32, Box<dyn Feature>)> = vec![
+            (multi_power, Box::new(Progress)),
+            (multi_power, Box::new(Blocking)),
+            (multi_power, Box::new(Edges)),
+            (single_power, Box::new(LeadingPiece)),
+        ];
+        // the table values stop telling the pieces apart once they crowd around the target camp
+        if endgame_power != 0. {
+            features.push((endgame_power, Box::new(Assignment)));
+        }
+        WeightedHeuristic {
+            name: "Complex".to_owned(),
+            tables: builder.build(config),
+            features,
+        }
     }
-    return 0.;
 }
 
-const PLAYER_WHITE_HEURISTIC_PROXIMITY: [[f32; 16]; 16] = [
-    [
-        4.8, 4.8, 4.5, 4.2, 3.8, 3.5, 3.2, 2.8, 2.5, 2.2, 1.8, 1.5, 1.2, 0.8, 0.5, 0.2,
-    ],
-    [
-        4.5, 4.5, 4.2, 3.8, 3.5, 3.2, 2.8, 2.5, 2.2, 1.8, 1.5, 1.2, 0.8, 0.5, 0.2, -0.2,
-    ],
-    [
-        4.2, 4.2, 3.8, 3.5, 3.2, 2.8, 2.5, 2.2, 1.8, 1.5, 1.2, 0.8, 0.5, 0.2, -0.2, -0.5,
-    ],
-    [
-        3.8, 3.8, 3.5, 3.2, 2.8, 2.5, 2.2, 1.8, 1.5, 1.2, 0.8, 0.5, 0.2, -0.2, -0.5, -0.8,
-    ],
-    [
-        3.5, 3.5, 3.2, 2.8, 2.5, 2.2, 1.8, 1.5, 1.2, 0.8, 0.5, 0.2, -0.2, -0.5, -0.8, -1.2,
-    ],
-    [
-        3.2, 3.2, 2.8, 2.5, 2.2, 1.8, 1.5, 1.2, 0.8, 0.5, 0.2, -0.2, -0.5, -0.8, -1.2, -1.5,
-    ],
-    [
-        2.8, 2.8, 2.5, 2.2, 1.8, 1.5, 1.2, 0.8, 0.5, 0.2, -0.2, -0.5, -0.8, -1.2, -1.5, -1.8,
-    ],
-    [
-        2.5, 2.5, 2.2, 1.8, 1.5, 1.2, 0.8, 0.5, 0.2, -0.2, -0.5, -0.8, -1.2, -1.5, -1.8, -2.2,
-    ],
-    [
-        2.2, 2.2, 1.8, 1.5, 1.2, 0.8, 0.5, 0.2, -0.2, -0.5, -0.8, -1.2, -1.5, -1.8, -2.2, -2.5,
-    ],
-    [
-        1.8, 1.8, 1.5, 1.2, 0.8, 0.5, 0.2, -0.2, -0.5, -0.8, -1.2, -1.5, -1.8, -2.2, -2.5, -2.8,
-    ],
-    [
-        1.5, 1.5, 1.2, 0.8, 0.5, 0.2, -0.2, -0.5, -0.8, -1.2, -1.5, -1.8, -2.2, -2.5, -2.8, -3.2,
-    ],
-    [
-        1.2, 1.2, 0.8, 0.5, 0.2, -0.2, -0.5, -0.8, -1.2, -1.5, -1.8, -2.2, -2.5, -2.8, -3.2, -3.5,
-    ],
-    [
-        0.8, 0.8, 0.5, 0.2, -0.2, -0.5, -0.8, -1.2, -1.5, -1.8, -2.2, -2.5, -2.8, -3.2, -3.5, -3.8,
-    ],
-    [
-        0.5, 0.5, 0.2, -0.2, -0.5, -0.8, -1.2, -1.5, -1.8, -2.2, -2.5, -2.8, -3.2, -3.5, -3.8, -4.2,
-    ],
-    [
-        0.2, 0.2, -0.2, -0.5, -0.8, -1.2, -1.5, -1.8, -2.2, -2.5, -2.8, -3.2, -3.5, -3.8, -4.2,
-        -4.5,
-    ],
-    [
-        -0.2, -0.2, -0.5, -0.8, -1.2, -1.5, -1.8, -2.2, -2.5, -2.8, -3.2, -3.5, -3.8, -4.2, -4.5,
-        -4.8,
-    ],
-];
-
-const PLAYER_BLACK_HEURISTIC_PROXIMITY: [[f32; 16]; 16] = [
-    [
-        -4.8, -4.5, -4.2, -3.8, -3.5, -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2,
-        -0.2,
-    ],
-    [
-        -4.5, -4.2, -3.8, -3.5, -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2,
-        0.2,
-    ],
-    [
-        -4.2, -3.8, -3.5, -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.5,
-    ],
-    [
-        -3.8, -3.5, -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 0.8,
-    ],
-    [
-        -3.5, -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.2,
-    ],
-    [
-        -3.2, -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.5,
-    ],
-    [
-        -2.8, -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 1.8,
-    ],
-    [
-        -2.5, -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.2,
-    ],
-    [
-        -2.2, -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.5,
-    ],
-    [
-        -1.8, -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 2.8,
-    ],
-    [
-        -1.5, -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.2,
-    ],
-    [
-        -1.2, -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.5, 3.5,
-    ],
-    [
-        -0.8, -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.5, 3.8, 3.8,
-    ],
-    [
-        -0.5, -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.5, 3.8, 4.2, 4.2,
-    ],
-    [
-        -0.2, 0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.5, 3.8, 4.2, 4.5, 4.5,
-    ],
-    [
-        0.2, 0.5, 0.8, 1.2, 1.5, 1.8, 2.2, 2.5, 2.8, 3.2, 3.5, 3.8, 4.2, 4.5, 4.8, 4.8,
-    ],
-];
-
-pub fn print_new_table() {
-    println!("[");
-    for y in 0..16 {
-        print!("[");
-        for x in 0..16 {
-            let mut score = PLAYER_BLACK_HEURISTIC_PROXIMITY[y][x];
-            if PLAYER_WHITE_BASE.contains(&Coords {
-                x: x as i8,
-                y: y as i8,
-            }) {
-                score += 0.1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::rc::Rc;
+
+    fn board() -> BoardConfig {
+        BoardConfig::named("8x8-10").unwrap()
+    }
+
+    #[test]
+    fn distance_metrics() {
+        let config = board();
+        let corner = [Coords { x: 0, y: 0 }];
+        for (metric, square, distance) in [
+            ("manhattan", Coords { x: 3, y: 4 }, 7.),
+            ("chebyshev", Coords { x: 3, y: 4 }, 4.),
+            ("euclidean", Coords { x: 3, y: 4 }, 5.),
+            // a step or a single jump each
+            ("moves", Coords { x: 2, y: 0 }, 1.),
+            ("moves", Coords { x: 2, y: 4 }, 2.),
+            ("moves", Coords { x: 7, y: 7 }, 4.),
+        ] {
+            let builder = TableBuilder {
+                metric: metric.parse().unwrap(),
+                ..TableBuilder::default()
+            };
+            let distances = builder.distances(&config, &corner);
+            assert_eq!(
+                distances[square.y as usize][square.x as usize], distance,
+                "{}",
+                metric
+            );
+        }
+        assert!("taxicab".parse::<DistanceMetric>().is_err());
+        assert!("middle".parse::<TableOrigin>().is_err());
+    }
+
+    #[test]
+    fn tables_reward_progress() {
+        let config = board();
+        for origin in ["corner", "target"] {
+            let builder = TableBuilder {
+                origin: origin.parse().unwrap(),
+                ..TableBuilder::default()
+            };
+            let tables = builder.build(&config);
+            // the values climb along the diagonal up to the target camp, the same for both players
+            for step in 0..6 {
+                let here = Coords { x: step, y: step };
+                let next = Coords {
+                    x: step + 1,
+                    y: step + 1,
+                };
+                assert!(tables.value(Player::Black, next) > tables.value(Player::Black, here));
+                let mirrored = Coords {
+                    x: 7 - step,
+                    y: 7 - step,
+                };
+                assert_eq!(
+                    tables.value(Player::Black, here),
+                    tables.value(Player::White, mirrored),
+                    "{}",
+                    origin
+                );
+            }
+        }
+
+        // ten points between the corners by default, the bonuses on top of them
+        let plain = TableBuilder::default().build(&config);
+        let (corner, far) = (Coords { x: 0, y: 0 }, Coords { x: 7, y: 7 });
+        assert!(
+            (plain.value(Player::Black, far) - plain.value(Player::Black, corner) - 10.).abs()
+                < 1e-4
+        );
+        let bonuses = TableBuilder {
+            target_bonus: 2.,
+            home_bonus: -3.,
+            ..TableBuilder::default()
+        }
+        .build(&config);
+        assert_eq!(
+            bonuses.value(Player::Black, far),
+            plain.value(Player::Black, far) + 2.
+        );
+        assert_eq!(
+            bonuses.value(Player::Black, corner),
+            plain.value(Player::Black, corner) - 3.
+        );
+        let middle = Coords { x: 4, y: 3 };
+        assert_eq!(
+            bonuses.value(Player::Black, middle),
+            plain.value(Player::Black, middle)
+        );
+    }
+
+    fn feature_names(heuristic: &WeightedHeuristic) -> Vec<String> {
+        heuristic
+            .features
+            .iter()
+            .map(|(_, feature)| feature.name())
+            .collect()
+    }
+
+    #[test]
+    fn presets_are_made_of_features() {
+        let config = board();
+        let tables = TableBuilder::default();
+        let leading = WeightedHeuristic::leading(&config, &tables, 2.5, 0.9);
+        assert_eq!(
+            feature_names(&leading),
+            ["progress", "blocking", "edges", "leading"]
+        );
+        let weights: Vec<f32> = leading.features.iter().map(|(weight, _)| *weight).collect();
+        assert_eq!(weights, [0.9, 0.9, 0.9, 2.5]);
+        assert!(!feature_names(&WeightedHeuristic::complex(
+            &config, &tables, 2.5, 0.9, 1., 0.
+        ))
+        .contains(&"assignment".to_owned()));
+        assert!(feature_names(&WeightedHeuristic::complex(
+            &config, &tables, 2.5, 0.9, 1., 1.
+        ))
+        .contains(&"assignment".to_owned()));
+
+        // discouraging the start lowers the home camp squares of the tables
+        let discourage = WeightedHeuristic::discourage(&config, &tables, 1.1, 2.);
+        let plain = tables.build(&config);
+        let corner = Coords { x: 0, y: 0 };
+        assert_eq!(
+            discourage.tables.value(Player::Black, corner),
+            plain.value(Player::Black, corner) - 2.
+        );
+    }
+
+    #[test]
+    fn scores_are_clamped() {
+        let config = Rc::new(board());
+        let mut board_state = BoardState::start_position(config.clone());
+        let mut weak = WeightedHeuristic::proximity(&config, &TableBuilder::default(), 1.);
+        let mut strong = WeightedHeuristic::proximity(&config, &TableBuilder::default(), 1000.);
+        for moved in [false, true] {
+            if moved {
+                board_state.coords_mut(Player::Black)[0] = Coords { x: 4, y: 4 };
+            }
+            for player in [Player::Black, Player::White] {
+                let score = weak.evaluate(&board_state, player, 0);
+                assert!(score.abs() < 100.);
+                assert_eq!(
+                    strong.evaluate(&board_state, player, 0),
+                    (1000. * score).clamp(-100., 100.)
+                );
             }
-            print!("{:.1}, ", score);
         }
-        println!("],")
     }
-    println!("]")
 }
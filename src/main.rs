@@ -1,62 +1,224 @@
+mod adjudication;
 mod decision_tree;
+mod features;
 mod halma;
+mod heuristic_config;
 mod heuristics;
 mod minimax;
+mod random_position;
+mod render;
+mod sprt;
+mod svg;
+mod symmetry;
+mod texel;
+mod tournament;
+mod tuning;
 
-use halma::board_state_from_str;
+use adjudication::Adjudication;
+use halma::{
+    game_record_from_str, position_from_str, position_to_string, BoardConfig, BoardState,
+    GameRecord, GameState, Player, Position, Rules,
+};
+use heuristic_config::{load_heuristic, parse_spec, REGISTRY};
+use heuristics::{ProximityTables, TableBuilder, WeightedHeuristic};
 
 use heuristics::Heuristic;
-use minimax::LogLevel;
+use minimax::{LogLevel, SearchFunction};
+use sprt::Sprt;
+use texel::{load_records, samples_from_records, TexelModel};
+use tournament::{run_tournament, Agent, MatchSettings};
+use tuning::{load_checkpoint, start_params, SpsaSettings, SpsaState};
 
-use std::{env, fs, time::Instant};
+use std::{env, error::Error, fmt::Display, fs, path::Path, rc::Rc, str::FromStr, time::Instant};
 
 use crate::{
     decision_tree::DecisionTreeNode,
     minimax::{alfa_beta, minimax},
+    random_position::{random_placement, random_playout},
+    svg::{write_game_svgs, SvgOptions},
+    symmetry::{canonical_position, mirror_position, symmetries},
 };
 
-fn run_test<A: Heuristic, B: Heuristic>(
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// optional files written after a game: `--record <file>`, `--svg <directory>`
+// and `--svg-values <player>` to draw that player's proximity table on the board
+struct GameOutputs {
+    record_file: Option<String>,
+    svg_directory: Option<String>,
+    svg_values: Option<Player>,
+}
+
+impl GameOutputs {
+    fn write(&self, record: &GameRecord) {
+        if let Some(record_file) = &self.record_file {
+            match fs::write(record_file, record.to_string()) {
+                Ok(()) => println!("Game record written to {}", record_file),
+                Err(error) => println!("Could not write game record: {}", error),
+            }
+        }
+        if let Some(svg_directory) = &self.svg_directory {
+            let tables = ProximityTables::new(&record.start.board_state.config);
+            let square_values = |coords| tables.value(self.svg_values.unwrap(), coords);
+            let options = SvgOptions {
+                square_values: match self.svg_values {
+                    Some(_) => Some(&square_values),
+                    None => None,
+                },
+                ..Default::default()
+            };
+            match write_game_svgs(record, Path::new(svg_directory), &options) {
+                Ok(()) => println!("SVG frames written to {}", svg_directory),
+                Err(error) => println!("Could not write SVG frames: {}", error),
+            }
+        }
+    }
+}
+
+// the player given by `--svg-values`, who has to take part in the game to have a table
+fn svg_values_flag(
+    args: &[String],
+    config: &BoardConfig,
+) -> Result<Option<Player>, Box<dyn Error>> {
+    match parse_flag::<Player>(args, "--svg-values")? {
+        Some(player) => {
+            if !config.players.contains(&player) {
+                return Err(format!("{} does not take part in this game", player.name()).into());
+            }
+            Ok(Some(player))
+        }
+        None => Ok(None),
+    }
+}
+
+// `--rounds <n>` stops the game after n rounds and `--adjudicate <method>[:margin]`
+// decides who won it, by distance to the target camps unless told otherwise
+struct GameLimits {
+    rounds_limit: Option<u32>,
+    adjudication: Adjudication,
+}
+
+// either a board file or the name of a variant to play from its start position
+fn load_position(board_file: &str) -> Result<Position, Box<dyn Error>> {
+    match BoardConfig::named(board_file) {
+        Some(config) => {
+            let first_player = config.players[0];
+            Ok(Position::new(
+                BoardState::start_position(Rc::new(config)),
+                first_player,
+            ))
+        }
+        None => {
+            let board_string = fs::read_to_string(board_file)
+                .map_err(|error| format!("Could not read board file {}: {}", board_file, error))?;
+            position_from_str(&board_string)
+        }
+    }
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|idx| args.get(idx + 1))
+}
+
+// `heuristics` holds one heuristic per player, in turn order
+fn run_test(
     function_str: &str,
-    heuristics: (&mut A, &mut B),
-    time: &mut Instant,
+    heuristics: &mut [&mut dyn Heuristic],
     first_node: DecisionTreeNode,
     max_depth: u32,
     log_level: &LogLevel,
-    rounds_limit: Option<u32>,
+    limits: &GameLimits,
+    outputs: &GameOutputs,
 ) {
-    *time = Instant::now();
+    let time = Instant::now();
     let mut node = first_node.clone();
-    let test_name = format!("{} vs {}", heuristics.0.name(), heuristics.1.name());
+    let names: Vec<String> = heuristics
+        .iter()
+        .map(|heuristic| heuristic.name())
+        .collect();
+    let test_name = names.join(" vs ");
+    let mut record = GameRecord::new(first_node.position);
 
     println!("Playing {}", test_name);
-    let finish: Option<(DecisionTreeNode, u32, u32)> = match function_str {
-        "minimax" => minimax(&mut node, max_depth, heuristics, rounds_limit, &log_level),
-        _ => alfa_beta(&mut node, max_depth, heuristics, rounds_limit, &log_level),
+    let finish = match function_str {
+        "minimax" => minimax(
+            &mut node,
+            max_depth,
+            heuristics,
+            limits.rounds_limit,
+            log_level,
+            &mut Some(&mut record),
+        ),
+        _ => alfa_beta(
+            &mut node,
+            max_depth,
+            heuristics,
+            limits.rounds_limit,
+            log_level,
+            &mut Some(&mut record),
+        ),
     };
     let elapsed = time.elapsed();
 
     println!("\n\n\n\n{} game finished", test_name);
     match finish {
-        Some((node, skipped, rounds)) => {
-            println!("Took {} rounds", &rounds + skipped);
+        Err(error) => println!("Search failed: {}", error),
+        Ok(Some((node, rounds))) => {
+            if let GameState::Draw(reason) = node.game_state {
+                println!("Game drawn: {}", reason);
+            }
+            println!("Took {} rounds", rounds);
+            println!("{}", node);
+        }
+        Ok(None) => {
+            let result = limits.adjudication.adjudicate(&node.position.board_state);
+            record.result = Some(result);
+            println!(
+                "Round limit reached, adjudicated by {}",
+                limits.adjudication.method
+            );
+            match result {
+                GameState::Won(player) => println!("{:?} wins", player),
+                _ => println!("Game drawn"),
+            }
             println!("{}", node);
         }
-        None => println!("Finished without winner"),
     }
     println!("Took {:.2?} seconds", elapsed.as_secs_f32());
+    outputs.write(&record);
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 5 {
-        panic!("Wrong number of arguments! Usage: cargo run --release -- <board filename> <log level [none, round, all]> <function [minimax, alfabeta]> <max depth>");
+// a game between the heuristics of the players, searched with the given function and depth
+fn game_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut position = board_arg(&args[1])?;
+    // `--rules orthogonal,own-jumps,...` overrides the rules of the board
+    if let Some(rules) = parse_flag::<Rules>(args, "--rules")? {
+        let mut config = (*position.board_state.config).clone();
+        config.rules = rules;
+        position.board_state.config = Rc::new(config);
+    }
+    let config = position.board_state.config.clone();
+    // `--handicap <player>:<n>` takes n pieces away from the player
+    if let Some(handicap) = flag_value(args, "--handicap") {
+        let parsed = handicap.split_once(':').and_then(|(player, count)| {
+            Some((player.parse::<Player>().ok()?, count.parse::<usize>().ok()?))
+        });
+        match parsed {
+            Some((player, count)) if config.players.contains(&player) => {
+                position.board_state.remove_pieces(player, count)
+            }
+            _ => return Err(format!("Could not parse handicap: {}", handicap).into()),
+        }
+    }
+    // `--first <player>` overrides who moves first
+    if let Some(player) = parse_flag::<Player>(args, "--first")? {
+        if !config.players.contains(&player) {
+            return Err(format!("{} does not take part in this game", player.name()).into());
+        }
+        position.to_move = player;
     }
-    let board_file = &args[1];
-    let board_string = fs::read_to_string(board_file)
-        .expect(format!("Could not read board file: {}", board_file).as_str());
-
-    let parsed_board = board_state_from_str(&board_string);
-
     let log_level: LogLevel = match args[2].as_str() {
         "none" => LogLevel::None,
         "all" => LogLevel::All,
@@ -65,100 +227,454 @@ fn main() {
 
     let function = &args[3];
 
-    let max_depth: u32 = args[4].parse().unwrap();
+    let max_depth: u32 = args[4]
+        .parse()
+        .map_err(|error| format!("Could not parse depth {}: {}", args[4], error))?;
+
+    let outputs = GameOutputs {
+        record_file: flag_value(args, "--record").cloned(),
+        svg_directory: flag_value(args, "--svg").cloned(),
+        svg_values: svg_values_flag(args, &config)?,
+    };
+
+    let limits = GameLimits {
+        rounds_limit: parse_flag(args, "--rounds")?,
+        adjudication: flag_or(args, "--adjudicate", Adjudication::default())?,
+    };
+
+    // `--black complex --white leading:single_power=3` picks the heuristic of each player,
+    // the others come from `--heuristics <name or file>,...` which goes around the board
+    // in turn order
+    let heuristic_names: Vec<&str> = flag_value(args, "--heuristics")
+        .map_or("leading,complex", |names| names.as_str())
+        .split(',')
+        .collect();
+    let mut seats: Vec<Box<dyn Heuristic>> = Vec::new();
+    for (seat, player) in config.players.iter().enumerate() {
+        let spec = match flag_value(args, &format!("--{}", player.name())) {
+            Some(spec) => spec.as_str(),
+            None => heuristic_names[seat % heuristic_names.len()],
+        };
+        let heuristic = load_heuristic(spec, &config)
+            .map_err(|error| format!("Could not load heuristic {}: {}", spec, error))?;
+        seats.push(Box::new(heuristic));
+    }
+
+    let first_node = DecisionTreeNode::new(position);
+
+    let mut players: Vec<&mut dyn Heuristic> = seats
+        .iter_mut()
+        .map(|heuristic| heuristic.as_mut() as &mut dyn Heuristic)
+        .collect();
+    run_test(
+        function,
+        &mut players,
+        first_node,
+        max_depth,
+        &log_level,
+        &limits,
+        &outputs,
+    );
+    Ok(())
+}
+
+// the value of `flag` parsed as `T`, if the flag is given
+fn parse_flag<T>(args: &[String], flag: &str) -> Result<Option<T>, Box<dyn Error>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match flag_value(args, flag) {
+        Some(value) => match value.parse() {
+            Ok(value) => Ok(Some(value)),
+            Err(error) => Err(format!("Could not parse {} {}: {}", flag, value, error).into()),
+        },
+        None => Ok(None),
+    }
+}
+
+fn flag_or<T>(args: &[String], flag: &str, default: T) -> Result<T, Box<dyn Error>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    Ok(parse_flag(args, flag)?.unwrap_or(default))
+}
+
+// the board every subcommand takes as its first argument
+fn board_arg(board_file: &str) -> Result<Position, Box<dyn Error>> {
+    load_position(board_file).map_err(|error| format!("Could not parse board: {}", error).into())
+}
+
+// `--function` and `--depth` of agents whose spec leaves them out
+fn agent_defaults(args: &[String]) -> Result<(SearchFunction, u32), Box<dyn Error>> {
+    Ok((
+        flag_or(args, "--function", SearchFunction::AlfaBeta)?,
+        flag_or(args, "--depth", 2)?,
+    ))
+}
 
-    let rng = rand::thread_rng();
-    let mut heuristic_random = heuristics::HeuristicRandom { rng: rng.clone() };
+fn parse_agents<'a>(
+    specs: impl Iterator<Item = &'a String>,
+    args: &[String],
+) -> Result<Vec<Agent>, Box<dyn Error>> {
+    let (function, depth) = agent_defaults(args)?;
+    specs
+        .map(|spec| {
+            Agent::parse(spec, function, depth)
+                .map_err(|error| format!("Could not parse agent {}: {}", spec, error).into())
+        })
+        .collect()
+}
 
-    let mut heuristic_proximity = heuristics::HeuristicProximity {
-        power: 1.08,
-        rng: rng.clone(),
+// `--openings`, `--rounds` and `--adjudicate` of every game of a match
+fn match_settings(args: &[String]) -> Result<MatchSettings, Box<dyn Error>> {
+    Ok(MatchSettings {
+        opening_plies: flag_or(args, "--openings", 4)?,
+        rounds_limit: Some(flag_or(args, "--rounds", 200)?),
+        adjudication: flag_or(args, "--adjudicate", Adjudication::default())?,
+    })
+}
+
+// the subcommands below report bad input and failures the same way
+fn report(result: Result<(), Box<dyn Error>>) {
+    if let Err(error) = result {
+        println!("{}", error);
+    }
+}
+
+// rendering an existing game record needs no search at all
+fn replay_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let record_string = fs::read_to_string(&args[2])
+        .map_err(|error| format!("Could not read record file {}: {}", args[2], error))?;
+    let record = game_record_from_str(&record_string)
+        .map_err(|error| format!("Could not parse game record: {}", error))?;
+    let outputs = GameOutputs {
+        record_file: None,
+        svg_directory: Some(flag_or(args, "--svg", "replay".to_owned())?),
+        svg_values: svg_values_flag(args, &record.start.board_state.config)?,
     };
+    outputs.write(&record);
+    Ok(())
+}
+
+// every mirror image of a position with the reference evaluation of the player to move,
+// which has to be the same for all of them, and the canonical one
+fn symmetry_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let position = board_arg(&args[2])?;
+    let mut reference =
+        WeightedHeuristic::proximity(&position.board_state.config, &TableBuilder::default(), 1.);
+    for symmetry in symmetries(&position.board_state.config) {
+        let mirrored = mirror_position(&position, symmetry).unwrap();
+        println!(
+            "{:?}, {:?} to move, evaluation {}\n{}",
+            symmetry,
+            mirrored.to_move,
+            reference.evaluate(&mirrored.board_state, mirrored.to_move, 0),
+            position_to_string(&mirrored)
+        );
+    }
+    println!(
+        "Canonical\n{}",
+        position_to_string(&canonical_position(&position))
+    );
+    Ok(())
+}
+
+// raw value of every feature for every player, before any weights
+fn features_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let position = board_arg(&args[2])?;
+    let board_state = &position.board_state;
+    let tables = ProximityTables::new(&board_state.config);
+    for mut feature in features::all() {
+        print!("{:>10}", feature.name());
+        for player in &board_state.config.players {
+            print!(
+                "  {} {:.2}",
+                player.name(),
+                feature.value(board_state, *player, &tables)
+            );
+        }
+        println!();
+    }
+    Ok(())
+}
 
-    let mut heuristic_leading = heuristics::HeuristicProximityWithSingle {
-        multi_power: 0.9,
-        single_power: 2.5,
-        rng: rng.clone(),
+// the proximity table of one player, generated with the given metric, origin, offset,
+// scale and camp bonuses, the defaults being the tables the heuristics use
+fn table_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let position = board_arg(&args[2])?;
+    let config = &position.board_state.config;
+    let builder = TableBuilder {
+        metric: flag_or(args, "--metric", TableBuilder::default().metric)?,
+        origin: flag_or(args, "--origin", TableBuilder::default().origin)?,
+        offset: parse_flag(args, "--offset")?,
+        scale: parse_flag(args, "--scale")?,
+        target_bonus: flag_or(args, "--target-bonus", 0.)?,
+        home_bonus: flag_or(args, "--home-bonus", 0.)?,
     };
+    let player = flag_or(args, "--player", config.players[0])?;
+    print!("{}", builder.build(config).table_to_string(config, player));
+    Ok(())
+}
+
+// every agent against every other one: `--pairs <n>` pairs of games with colours swapped,
+// each pair after its own `--openings <plies>` random moves
+fn tournament_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let position = board_arg(&args[2])?;
+    let agents = parse_agents(
+        args[3..].iter().take_while(|arg| !arg.starts_with("--")),
+        args,
+    )?;
+    let settings = match_settings(args)?;
+    let pairs = flag_or(args, "--pairs", 1)?;
+    let seed = flag_or(args, "--seed", rand::thread_rng().gen())?;
+    let tournament = run_tournament(
+        &position,
+        agents,
+        pairs,
+        &settings,
+        &mut StdRng::seed_from_u64(seed),
+    );
+    eprintln!("Seed: {}", seed);
+    let tournament = tournament.map_err(|error| format!("Tournament failed: {}", error))?;
+    println!("\n{}", tournament);
+    Ok(())
+}
 
-    let mut heuristic_discourage = heuristics::HeuristicDiscourageStart {
-        other_power: 1.1,
-        discourage_power: 1.0,
+// sequential probability ratio test of the first agent against the second one,
+// `--elo0`/`--elo1` being the Elo advantages of the two hypotheses
+fn sprt_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let position = board_arg(&args[2])?;
+    let specs: Vec<&String> = args[3..]
+        .iter()
+        .take_while(|arg| !arg.starts_with("--"))
+        .collect();
+    if specs.len() != 2 {
+        return Err(format!("Expected two agents, got {}", specs.len()).into());
+    }
+    let agents = parse_agents(specs.into_iter(), args)?;
+    let sprt = Sprt {
+        elo0: flag_or(args, "--elo0", 0.)?,
+        elo1: flag_or(args, "--elo1", 50.)?,
+        alpha: flag_or(args, "--alpha", 0.05)?,
+        beta: flag_or(args, "--beta", 0.05)?,
     };
+    let settings = match_settings(args)?;
+    let max_games = flag_or(args, "--max-games", 1000)?;
+    let seed = flag_or(args, "--seed", rand::thread_rng().gen())?;
+    let test = sprt.run(
+        &position,
+        &agents[0],
+        &agents[1],
+        max_games,
+        &settings,
+        &mut StdRng::seed_from_u64(seed),
+    );
+    eprintln!("Seed: {}", seed);
+    let (decision, result) = test.map_err(|error| format!("Test failed: {}", error))?;
+    println!(
+        "\n{} vs {}: {}, {}",
+        agents[0].name, agents[1].name, result, decision
+    );
+    Ok(())
+}
 
-    let mut heuristic_complex = heuristics::HeuristicComplex {
-        single_power: 2.5,
-        multi_power: 0.9,
-        discourage_power: 1.0,
+// SPSA tuning of the parameters of a registered heuristic by self-play, checked against
+// `--baseline` at the end; `--checkpoint <file>` is resumed from if it exists
+fn tune_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let position = board_arg(&args[2])?;
+    let (heuristic, overrides) =
+        parse_spec(&args[3]).map_err(|error| format!("Could not tune {}: {}", args[3], error))?;
+    let (function, depth) = agent_defaults(args)?;
+    let seed = flag_or(args, "--seed", rand::thread_rng().gen())?;
+    let mut settings = SpsaSettings {
+        iterations: flag_or(args, "--iterations", 50)?,
+        pairs: flag_or(args, "--pairs", 2)?,
+        learning_rate: flag_or(args, "--learning-rate", 0.05)?,
+        perturbation: flag_or(args, "--perturbation", 0.1)?,
+        seed,
+        function,
+        depth,
+        checkpoint: parse_flag(args, "--checkpoint")?,
+        out: parse_flag(args, "--out")?,
+    };
+    let match_settings = match_settings(args)?;
+    let check_pairs = flag_or(args, "--check-pairs", 10)?;
+    let state = match load_checkpoint(&settings)
+        .map_err(|error| format!("Could not read the checkpoint: {}", error))?
+    {
+        Some(state) => {
+            println!("Resuming from iteration {}", state.iteration);
+            settings.seed = state.seed;
+            state
+        }
+        None => {
+            let params = start_params(heuristic, &overrides)
+                .map_err(|error| format!("Could not tune {}: {}", args[3], error))?;
+            SpsaState::new(heuristic, &params, seed)
+        }
     };
+    let baseline = flag_value(args, "--baseline")
+        .cloned()
+        .unwrap_or(state.heuristic.clone());
+    let tuned = settings
+        .run(&position, state, &match_settings)
+        .and_then(|state| {
+            let result =
+                settings.compare(&position, &state, &baseline, check_pairs, &match_settings)?;
+            Ok((state, result))
+        });
+    eprintln!("Seed: {}", settings.seed);
+    let (state, result) = tuned.map_err(|error| format!("Tuning failed: {}", error))?;
+    let (elo, low, high) = result.elo();
+    println!(
+        "\n{} against {}: {}, Elo {:+.0} ({:+.0}, {:+.0})",
+        state.spec(&state.params),
+        baseline,
+        result,
+        elo,
+        low,
+        high
+    );
+    Ok(())
+}
 
-    //heuristics::print_new_table();
-
-    match parsed_board {
-        Ok(board) => {
-            let first_node =
-                DecisionTreeNode::new(board, halma::GameState::Start(halma::Player::Black));
-            let mut now = Instant::now();
-            // run_test(
-            //     &function,
-            //     (&mut heuristic_random, &mut heuristic_leading),
-            //     &mut now,
-            //     first_node.clone(),
-            //     max_depth,
-            //     &log_level,
-            //     None,
-            // );
-            // let mut new_proximity = heuristic_proximity.clone();
-
-            // run_test(
-            //     &function,
-            //     (&mut heuristic_random, &mut heuristic_discourage),
-            //     &mut now,
-            //     first_node.clone(),
-            //     max_depth,
-            //     &log_level,
-            //     None,
-            // );
-
-            // run_test(
-            //     &function,
-            //     (&mut heuristic_proximity, &mut heuristic_proximity_hybrid),
-            //     &mut now,
-            //     first_node.clone(),
-            //     max_depth,
-            //     &log_level,
-            //     None,
-            // );
-            // run_test(
-            //     &function,
-            //     (&mut heuristic_proximity, &mut heuristic_discourage),
-            //     &mut now,
-            //     first_node.clone(),
-            //     max_depth,
-            //     &log_level,
-            //     None,
-            // );
-
-            // run_test(
-            //     &function,
-            //     (&mut heuristic_proximity_hybrid, &mut heuristic_discourage),
-            //     &mut now,
-            //     first_node.clone(),
-            //     max_depth,
-            //     &log_level,
-            //     None,
-            // );
-
-            run_test(
-                &function,
-                (&mut heuristic_leading, &mut heuristic_complex),
-                &mut now,
-                first_node.clone(),
-                max_depth,
-                &log_level,
-                None,
-            );
+// fits table values and feature weights to the results of recorded games,
+// `--out <file>` getting them as a heuristic file
+fn texel_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let paths: Vec<String> = args[2..]
+        .iter()
+        .take_while(|arg| !arg.starts_with("--"))
+        .cloned()
+        .collect();
+    let epochs = flag_or(args, "--epochs", 200)?;
+    let learning_rate = flag_or(args, "--learning-rate", 0.01)?;
+    let (samples, config) = load_records(&paths)
+        .and_then(|records| samples_from_records(&records))
+        .map_err(|error| format!("Could not load the games: {}", error))?;
+    let mut model = TexelModel::new(config);
+    model.fit_k(&samples);
+    println!(
+        "{} positions, k {:.4}, starting error {:.6}",
+        samples.len(),
+        model.k,
+        model.error(&samples)
+    );
+    model.train(&samples, epochs, learning_rate);
+    for feature in model.heuristic_config().features.iter().skip(1) {
+        println!("{}: {}", feature.feature, feature.weight);
+    }
+    let heuristic = model
+        .to_toml()
+        .map_err(|error| format!("Could not write the heuristic: {}", error))?;
+    match flag_value(args, "--out") {
+        Some(out) => {
+            fs::write(out, heuristic)
+                .map_err(|error| format!("Could not write the heuristic: {}", error))?;
+            println!("Heuristic written to {}", out);
+        }
+        None => print!("{}", heuristic),
+    }
+    Ok(())
+}
+
+// a corpus of random positions: `--plies <n>` random moves from the given position,
+// or `--place <pieces>` pieces of every player put on random squares
+fn random_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let position = board_arg(&args[2])?;
+    let seed = flag_or(args, "--seed", rand::thread_rng().gen())?;
+    let count = flag_or(args, "--count", 1)?;
+    let place: Option<usize> = parse_flag(args, "--place")?;
+    let plies = flag_or(args, "--plies", 20)?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let out_directory = flag_value(args, "--out");
+    if let Some(out_directory) = out_directory {
+        fs::create_dir_all(out_directory)
+            .map_err(|error| format!("Could not create {}: {}", out_directory, error))?;
+    }
+    for idx in 0..count {
+        let random = match place {
+            Some(pieces) => random_placement(&position.board_state, pieces, &mut rng),
+            None => random_playout(&position, plies, &mut rng),
+        };
+        let random_string = position_to_string(&random);
+        match out_directory {
+            Some(out_directory) => {
+                let file = Path::new(out_directory).join(format!("position_{:04}.txt", idx));
+                fs::write(&file, random_string)
+                    .map_err(|error| format!("Could not write {}: {}", file.display(), error))?;
+            }
+            None => println!("{}", random_string),
         }
-        Err(error) => println!("Could not parse board: {}", error),
     }
+    eprintln!("Seed: {}", seed);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    // rendering an existing game record needs no search at all
+    if args.len() >= 3 && args[1] == "replay" {
+        report(replay_command(&args));
+        return;
+    }
+    // the built-in start positions, in the board file format
+    if args.len() >= 2 && args[1] == "variants" {
+        for name in BoardConfig::VARIANTS {
+            let config = BoardConfig::named(name).unwrap();
+            let first_player = config.players[0];
+            let position = Position::new(BoardState::start_position(Rc::new(config)), first_player);
+            println!("{}\n{}", name, position_to_string(&position));
+        }
+        return;
+    }
+    if args.len() >= 3 && args[1] == "symmetry" {
+        report(symmetry_command(&args));
+        return;
+    }
+    if args.len() >= 3 && args[1] == "table" {
+        report(table_command(&args));
+        return;
+    }
+    if args.len() >= 3 && args[1] == "features" {
+        report(features_command(&args));
+        return;
+    }
+    // the heuristics which can be selected by name and their default parameters
+    if args.len() >= 2 && args[1] == "heuristics" {
+        for registered in &REGISTRY {
+            let params: Vec<String> = registered
+                .params
+                .iter()
+                .map(|(param, default)| format!("{}={}", param, default))
+                .collect();
+            println!("{} {}", registered.name, params.join(":"));
+        }
+        return;
+    }
+    if args.len() >= 5 && args[1] == "tournament" {
+        report(tournament_command(&args));
+        return;
+    }
+    if args.len() >= 5 && args[1] == "sprt" {
+        report(sprt_command(&args));
+        return;
+    }
+    if args.len() >= 4 && args[1] == "tune" {
+        report(tune_command(&args));
+        return;
+    }
+    if args.len() >= 3 && args[1] == "texel" {
+        report(texel_command(&args));
+        return;
+    }
+    if args.len() >= 3 && args[1] == "random" {
+        report(random_command(&args));
+        return;
+    }
+    if args.len() < 5 {
+        panic!("Wrong number of arguments! Usage: cargo run --release -- <board filename or variant [16x16, 16x16-4p, 8x8-10, 8x8-13, 10x10, 10x10-4p, star, star-3p, star-4p, star-6p]> <log level [none, round, all]> <function [minimax, alfabeta]> <max depth> [--rules <orthogonal,own-jumps,leave-target,no-reentry,blocked-win,blocked=<pass|loss|draw>,vacate-home=N,repetition=N,no-progress=N,ply-cap=N>] [--handicap <player>:<n>] [--first <player>] [--rounds <n>] [--adjudicate <target|distance|heuristic>[:margin]] [--heuristics <heuristic>,...] [--<player> <heuristic>], where <heuristic> is <name or toml file>[:<parameter>=<value>...] [--record <file>] [--svg <directory>] [--svg-values <player>]\n       cargo run --release -- replay <record file> [--svg <directory>] [--svg-values <player>]\n       cargo run --release -- variants\n       cargo run --release -- symmetry <board filename or variant>\n       cargo run --release -- heuristics\n       cargo run --release -- features <board filename or variant>\n       cargo run --release -- table <board filename or variant> [--player <player>] [--metric <manhattan|chebyshev|euclidean|moves>] [--origin <corner|target>] [--offset <x>] [--scale <x>] [--target-bonus <x>] [--home-bonus <x>]\n       cargo run --release -- tournament <board filename or variant> <agent> <agent>... [--pairs <n>] [--openings <plies>] [--seed <n>] [--rounds <n>] [--adjudicate <method>[:margin]] [--function <minimax|alfabeta>] [--depth <n>], where <agent> is <heuristic>[@<minimax|alfabeta>][@<depth>]\n       cargo run --release -- sprt <board filename or variant> <agent> <agent> [--elo0 <elo>] [--elo1 <elo>] [--alpha <p>] [--beta <p>] [--max-games <n>] [--openings <plies>] [--seed <n>] [--rounds <n>] [--adjudicate <method>[:margin]] [--function <minimax|alfabeta>] [--depth <n>]\n       cargo run --release -- tune <board filename or variant> <heuristic name>[:<parameter>=<value>...] [--iterations <n>] [--pairs <n>] [--learning-rate <x>] [--perturbation <x>] [--seed <n>] [--checkpoint <file>] [--out <file>] [--baseline <heuristic>] [--check-pairs <n>] [--openings <plies>] [--rounds <n>] [--adjudicate <method>[:margin]] [--function <minimax|alfabeta>] [--depth <n>]\n       cargo run --release -- texel <record file or directory>... [--epochs <n>] [--learning-rate <x>] [--out <file>]\n       cargo run --release -- random <board filename or variant> [--plies <n> | --place <pieces>] [--seed <n>] [--count <n>] [--out <directory>]");
+    }
+    report(game_command(&args));
 }
@@ -1,374 +1,540 @@
 use crate::decision_tree::DecisionTreeNode;
-use crate::halma::{GameState, Player};
+use crate::halma::{DrawTracker, GameRecord, GameState, Player};
 
 use crate::heuristics::Heuristic;
 
-#[derive(Clone)]
-enum MinMaxResult {
-    Eval(f32, usize),
-    Leaf(DecisionTreeNode, u32),
+use std::{error::Error, fmt::Display, str::FromStr};
+
+// a won or lost game scores beyond anything the heuristics return, which are clamped to ±100
+const WIN_SCORE: f32 = 1000.;
+
+// the score of a finished game for `player`; the more depth is left, the sooner the game ends,
+// so a quick win beats a slow one and a slow loss beats a quick one
+fn game_over_score(game_state: GameState, player: Player, depth_left: u32) -> f32 {
+    match game_state {
+        GameState::Won(winner) if winner == player => WIN_SCORE + depth_left as f32,
+        GameState::Won(_) => -WIN_SCORE - depth_left as f32,
+        _ => 0.,
+    }
+}
+
+#[derive(Debug)]
+pub enum SearchError {
+    // the node passed to the search is already won or drawn
+    GameOver(GameState),
+    // every player in the turn order needs a heuristic
+    MissingHeuristics { players: usize, heuristics: usize },
+    // a search has to look at least one move ahead to pick a move
+    ZeroDepth,
+}
+
+impl Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchError::GameOver(game_state) => {
+                write!(f, "The game is already over: {:?}", game_state)
+            }
+            SearchError::MissingHeuristics {
+                players,
+                heuristics,
+            } => write!(
+                f,
+                "{} players need as many heuristics, got {}",
+                players, heuristics
+            ),
+            SearchError::ZeroDepth => write!(f, "The search depth has to be at least 1"),
+        }
+    }
 }
 
+impl Error for SearchError {}
+
 pub enum LogLevel {
     None,
     RoundNum,
     All,
 }
 
-pub fn minimax<A: Heuristic, B: Heuristic>(
+// the evaluation of `node` for `player` and the index of the child it comes from
+fn minimax_inner(
     node: &mut DecisionTreeNode,
-    max_depth: u32,
-    heuristics: (&mut A, &mut B),
-    rounds_limit: Option<u32>,
-    log_level: &LogLevel,
-) -> Option<(DecisionTreeNode, u32, u32)> {
-    fn minimax_inner(
-        node: &mut DecisionTreeNode,
-        current_depth: u32,
-        heuristic: &mut impl Heuristic,
-        node_index: usize,
-        maximizing: bool,
-        player: Player,
-        round_number: u32,
-    ) -> MinMaxResult {
-        if current_depth == 0 {
-            return MinMaxResult::Eval(
-                heuristic.evaluate(&node.board_state, player, round_number),
-                node_index,
-            );
-        }
-
-        let next_player = match node.game_state {
-            GameState::Start(player) => player,
-            GameState::Moved(player) => player.other(),
-            GameState::Won(_) => {
-                return MinMaxResult::Leaf(node.clone(), current_depth);
-            }
-        };
+    current_depth: u32,
+    heuristic: &mut dyn Heuristic,
+    node_index: usize,
+    player: Player,
+    round_number: u32,
+) -> (f32, usize) {
+    if node.game_state != GameState::InProgress {
+        return (
+            game_over_score(node.game_state, player, current_depth),
+            node_index,
+        );
+    }
+    if current_depth == 0 {
+        return (
+            heuristic.evaluate(&node.position.board_state, player, round_number),
+            node_index,
+        );
+    }
+    let next_player = node.position.to_move;
 
-        let mut max_eval: f32 = match maximizing {
-            true => f32::NEG_INFINITY,
-            false => f32::INFINITY,
-        };
+    let maximizing = next_player == player;
+    let mut max_eval: f32 = match maximizing {
+        true => f32::NEG_INFINITY,
+        false => f32::INFINITY,
+    };
 
-        if !node.generated {
-            node.generate_children(next_player);
-        }
+    if !node.generated {
+        node.generate_children();
+    }
 
-        if node.children.len() == 0 {
-            println!("Node with no children found! {}", &node);
-            println!("Ensuring generation");
-            node.generate_children(next_player);
-            panic!("{}", &node);
-        }
+    // the rules ended the game for a player left without moves
+    if node.children.is_empty() {
+        return (
+            game_over_score(node.game_state, player, current_depth),
+            node_index,
+        );
+    }
 
-        let mut max_child_index = 0;
-        let mut child_index = 0;
+    let mut max_child_index = 0;
 
-        for mut child in &mut node.children {
-            let minmax_inner_result = minimax_inner(
-                &mut child,
-                current_depth - 1,
-                heuristic,
-                child_index,
-                !maximizing,
-                player,
-                round_number,
-            );
-            match minmax_inner_result {
-                MinMaxResult::Eval(child_eval, _) => match maximizing {
-                    false => {
-                        if max_eval > child_eval {
-                            max_eval = child_eval;
-                            max_child_index = child_index;
-                        }
-                    }
-                    true => {
-                        if max_eval < child_eval {
-                            max_eval = child_eval;
-                            max_child_index = child_index;
-                        }
-                    }
-                },
-                MinMaxResult::Leaf(_, _) => return minmax_inner_result,
+    for (child_index, child) in node.children.iter_mut().enumerate() {
+        let (child_eval, _) = minimax_inner(
+            child,
+            current_depth - 1,
+            heuristic,
+            child_index,
+            player,
+            round_number,
+        );
+        match maximizing {
+            false => {
+                if max_eval > child_eval {
+                    max_eval = child_eval;
+                    max_child_index = child_index;
+                }
+            }
+            true => {
+                if max_eval < child_eval {
+                    max_eval = child_eval;
+                    max_child_index = child_index;
+                }
             }
-
-            child_index += 1;
         }
+    }
+
+    (max_eval, max_child_index)
+}
 
-        return MinMaxResult::Eval(max_eval, max_child_index);
+// the search state travels down the recursion as plain arguments, the window included
+#[allow(clippy::too_many_arguments)]
+fn alfa_beta_inner(
+    node: &mut DecisionTreeNode,
+    current_depth: u32,
+    heuristic: &mut dyn Heuristic,
+    node_index: usize,
+    mut alfa: f32,
+    mut beta: f32,
+    player: Player,
+    round_number: u32,
+) -> (f32, usize) {
+    if node.game_state != GameState::InProgress {
+        return (
+            game_over_score(node.game_state, player, current_depth),
+            node_index,
+        );
     }
+    let next_player = node.position.to_move;
 
-    let mut result: Option<MinMaxResult> = None;
-    let mut rounds: u32 = 0;
-    let mut player = match node.game_state{
-        GameState::Start(player) => player,
-        GameState::Moved(player) => player,
-        GameState::Won(_) => panic!("Passed a winning node to minimax, probably a mistake!"),
+    if current_depth == 0 {
+        return (
+            heuristic.evaluate(&node.position.board_state, player, round_number),
+            node_index,
+        );
+    }
+
+    let maximizing = next_player == player;
+    let mut max_eval: f32 = match maximizing {
+        true => f32::NEG_INFINITY,
+        false => f32::INFINITY,
     };
-    let mut current_heuristic = false;
 
-    loop {
-        match log_level {
-            LogLevel::None => {}
-            _ => {
-                println!("Playing round {}", rounds);
-            }
-        }
-        match rounds_limit {
-            Some(limit) => {
-                if rounds == limit {
-                    return None;
+    if !node.generated {
+        node.generate_children();
+    }
+
+    let mut max_child_index = 0;
+    // the rules ended the game for a player left without moves
+    if node.children.is_empty() {
+        return (
+            game_over_score(node.game_state, player, current_depth),
+            node_index,
+        );
+    }
+    for (child_index, child) in node.children.iter_mut().enumerate() {
+        let (child_eval, _) = alfa_beta_inner(
+            child,
+            current_depth - 1,
+            heuristic,
+            child_index,
+            alfa,
+            beta,
+            player,
+            round_number,
+        );
+        match maximizing {
+            false => {
+                if max_eval > child_eval {
+                    max_eval = child_eval;
+                    max_child_index = child_index;
                 }
-            }
-            None => {}
-        }
-        match result {
-            Some(some_result) => match some_result {
-                MinMaxResult::Eval(eval, eval_node) => {
-                    *node = node.children.swap_remove(eval_node);
-                    if matches!(log_level, LogLevel::All) {
-                        println!("Evaluated node is: {} with score: {}", eval_node, eval);
-                        match current_heuristic {
-                            false => println!("Evaluated with heur.: {}", heuristics.0.name()),
-                            true => println!("Evaluated with heur.: {}", heuristics.1.name()),
-                        }
-                        println!("Children of node: {}", &node.children.len());
-                        println!("{}", &node);
-                    }
-
-                    player = player.other();
-                    current_heuristic = !current_heuristic;
-
-                    result = match current_heuristic {
-                        true => Some(minimax_inner(
-                            node,
-                            max_depth,
-                            heuristics.0,
-                            0,
-                            true,
-                            player,
-                            rounds,
-                        )),
-                        false => Some(minimax_inner(
-                            node,
-                            max_depth,
-                            heuristics.1,
-                            0,
-                            true,
-                            player,
-                            rounds,
-                        )),
-                    }
+                if beta > child_eval {
+                    beta = child_eval;
                 }
-                MinMaxResult::Leaf(final_node, from_depth) => {
-                    return Some((final_node, max_depth - from_depth, rounds))
+            }
+            true => {
+                if max_eval < child_eval {
+                    max_eval = child_eval;
+                    max_child_index = child_index;
                 }
-            },
-            None => {
-                if matches!(log_level, LogLevel::All) {
-                    println!("First node:\n{}", &node);
+                if alfa < child_eval {
+                    alfa = child_eval;
                 }
-                result = Some(minimax_inner(
-                    node,
-                    max_depth,
-                    heuristics.0,
-                    0,
-                    true,
-                    player,
-                    rounds,
-                ))
             }
         }
-        rounds += 1;
+
+        if beta <= alfa {
+            break;
+        }
     }
+
+    (max_eval, max_child_index)
 }
-pub fn alfa_beta<A: Heuristic, B: Heuristic>(
+
+// plays the child the search picked; the game is over when the move won it, when the
+// search found the player to move blocked with the rules ending the game, or when the
+// draw rules call it
+fn play_searched_move(
+    node: &mut DecisionTreeNode,
+    child_index: usize,
+    draws: &mut DrawTracker,
+    record: &mut Option<&mut GameRecord>,
+) -> bool {
+    if node.game_state == GameState::InProgress {
+        *node = node.children.swap_remove(child_index);
+        if let (Some(record), Some(last_move)) = (record.as_mut(), &node.position.last_move) {
+            record.moves.push(last_move.clone());
+        }
+        if node.game_state == GameState::InProgress {
+            if let Some(reason) = draws.update(&node.position) {
+                node.game_state = GameState::Draw(reason);
+            }
+        }
+    }
+    if node.game_state == GameState::InProgress {
+        return false;
+    }
+    if let Some(record) = record.as_mut() {
+        record.result = Some(node.game_state);
+    }
+    true
+}
+
+// `heuristics` holds one heuristic per player, in the turn order of the board config;
+// with more than two players the search is paranoid: the player to move maximizes
+// their own evaluation and assumes every opponent is minimizing it
+pub fn minimax(
     node: &mut DecisionTreeNode,
     max_depth: u32,
-    heuristics: (&mut A, &mut B),
+    heuristics: &mut [&mut dyn Heuristic],
     rounds_limit: Option<u32>,
     log_level: &LogLevel,
-) -> Option<(DecisionTreeNode, u32, u32)> {
-    fn alfa_beta_inner(
-        node: &mut DecisionTreeNode,
-        current_depth: u32,
-        heuristic: &mut impl Heuristic,
-        node_index: usize,
-        maximizing: bool,
-        mut alfa: f32,
-        mut beta: f32,
-        player: Player,
-        round_number: u32,
-    ) -> MinMaxResult {
-        let next_player = match node.game_state {
-            GameState::Start(game_player) => game_player,
-            GameState::Moved(game_player) => game_player.other(),
-            GameState::Won(_) => {
-                return MinMaxResult::Leaf(node.clone(), current_depth);
-            }
-        };
-
-        if current_depth == 0 {
-            //println!("Evaluating from the perspective of {:?}", player);
-            return MinMaxResult::Eval(
-                heuristic.evaluate(&node.board_state, player, round_number),
-                node_index,
-            );
-        }
+    record: &mut Option<&mut GameRecord>,
+) -> Result<Option<(DecisionTreeNode, u32)>, SearchError> {
+    search_game(
+        node,
+        SearchFunction::Minimax,
+        max_depth,
+        heuristics,
+        rounds_limit,
+        log_level,
+        record,
+    )
+}
 
-        let mut max_eval: f32 = match maximizing {
-            true => f32::NEG_INFINITY,
-            false => f32::INFINITY,
-        };
+pub fn alfa_beta(
+    node: &mut DecisionTreeNode,
+    max_depth: u32,
+    heuristics: &mut [&mut dyn Heuristic],
+    rounds_limit: Option<u32>,
+    log_level: &LogLevel,
+    record: &mut Option<&mut GameRecord>,
+) -> Result<Option<(DecisionTreeNode, u32)>, SearchError> {
+    search_game(
+        node,
+        SearchFunction::AlfaBeta,
+        max_depth,
+        heuristics,
+        rounds_limit,
+        log_level,
+        record,
+    )
+}
 
-        if !node.generated {
-            node.generate_children(next_player);
-        }
+// one search of `function` from `node` for the player to move
+fn search(
+    node: &mut DecisionTreeNode,
+    function: SearchFunction,
+    depth: u32,
+    heuristic: &mut dyn Heuristic,
+    round_number: u32,
+) -> (f32, usize) {
+    let player = node.position.to_move;
+    match function {
+        SearchFunction::Minimax => minimax_inner(node, depth, heuristic, 0, player, round_number),
+        SearchFunction::AlfaBeta => alfa_beta_inner(
+            node,
+            depth,
+            heuristic,
+            0,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+            player,
+            round_number,
+        ),
+    }
+}
 
-        let mut max_child_index = 0;
-        let mut child_index = 0;
-        if node.children.len() == 0 {
-            println!("Node with no children found! {}", &node);
-            println!("Ensuring generation");
-            node.generate_children(next_player);
-            panic!("{}", &node);
+// plays the game out with logging; returns the final node and the number of moves played,
+// or `None` when the rounds limit came first
+fn search_game(
+    node: &mut DecisionTreeNode,
+    function: SearchFunction,
+    max_depth: u32,
+    heuristics: &mut [&mut dyn Heuristic],
+    rounds_limit: Option<u32>,
+    log_level: &LogLevel,
+    record: &mut Option<&mut GameRecord>,
+) -> Result<Option<(DecisionTreeNode, u32)>, SearchError> {
+    if node.game_state != GameState::InProgress {
+        return Err(SearchError::GameOver(node.game_state));
+    }
+    if max_depth == 0 {
+        return Err(SearchError::ZeroDepth);
+    }
+    let config = node.position.board_state.config.clone();
+    let mut draws = DrawTracker::new(&node.position);
+    if heuristics.len() < config.players.len() {
+        return Err(SearchError::MissingHeuristics {
+            players: config.players.len(),
+            heuristics: heuristics.len(),
+        });
+    }
+    if matches!(log_level, LogLevel::All) {
+        println!("First node:\n{}", &node);
+    }
+    let mut rounds: u32 = 0;
+    loop {
+        if !matches!(log_level, LogLevel::None) {
+            println!("Playing round {}", rounds);
         }
-        for mut child in &mut node.children {
-            let minmax_inner_result = alfa_beta_inner(
-                &mut child,
-                current_depth - 1,
-                heuristic,
-                child_index,
-                !maximizing,
-                alfa,
-                beta,
-                player,
-                round_number,
-            );
-            match minmax_inner_result {
-                MinMaxResult::Eval(child_eval, _) => match maximizing {
-                    false => {
-                        if max_eval > child_eval {
-                            max_eval = child_eval;
-                            max_child_index = child_index;
-                        }
-                        if beta > child_eval {
-                            beta = child_eval;
-                        }
-                    }
-                    true => {
-                        if max_eval < child_eval {
-                            max_eval = child_eval;
-                            max_child_index = child_index;
-                        }
-                        if alfa < child_eval {
-                            alfa = child_eval;
-                        }
-                    }
-                },
-
-                MinMaxResult::Leaf(_, _) => return minmax_inner_result,
-            }
-
-            if beta <= alfa {
-                break;
-            }
-            child_index += 1;
+        if rounds_limit == Some(rounds) {
+            return Ok(None);
+        }
+        let seat = config.seat(node.position.to_move);
+        let (eval, eval_node) = search(node, function, max_depth, &mut *heuristics[seat], rounds);
+        let moved = node.game_state == GameState::InProgress;
+        let finished = play_searched_move(node, eval_node, &mut draws, record);
+        if moved {
+            rounds += 1;
+        }
+        if matches!(log_level, LogLevel::All) && moved {
+            println!("Evaluated node is: {} with score: {}", eval_node, eval);
+            println!("Evaluated with heur.: {}", heuristics[seat].name());
+            println!("Children of node: {}", &node.children.len());
+            println!("{}", &node);
         }
+        if finished {
+            return Ok(Some((node.clone(), rounds)));
+        }
+    }
+}
 
-        return MinMaxResult::Eval(max_eval, max_child_index);
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchFunction {
+    Minimax,
+    AlfaBeta,
+}
+
+impl Display for SearchFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchFunction::Minimax => write!(f, "minimax"),
+            SearchFunction::AlfaBeta => write!(f, "alfabeta"),
+        }
     }
+}
 
-    let mut result: Option<MinMaxResult> = None;
-    let mut rounds: u32 = 0;
+impl FromStr for SearchFunction {
+    type Err = Box<dyn Error>;
 
-    let mut current_heuristic = false;
-    let mut player = match node.game_state{
-        GameState::Start(player) => player,
-        GameState::Moved(player) => player,
-        GameState::Won(_) => panic!("Passed a winning node to alfabeta, probably a mistake!"),
-    };
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minimax" => Ok(SearchFunction::Minimax),
+            "alfabeta" => Ok(SearchFunction::AlfaBeta),
+            _ => Err(format!("Unknown search function: {}", s).into()),
+        }
+    }
+}
+
+// how one player of `play_game` picks their moves
+pub struct Seat<'a> {
+    pub function: SearchFunction,
+    pub depth: u32,
+    pub heuristic: &'a mut dyn Heuristic,
+}
 
+// plays a game without any logging where every player searches with their own function
+// and depth; `seats` are in the turn order of the board config and the result is the same
+// as the one of `minimax` and `alfa_beta`
+pub fn play_game(
+    node: &mut DecisionTreeNode,
+    seats: &mut [Seat],
+    rounds_limit: Option<u32>,
+    record: &mut Option<&mut GameRecord>,
+) -> Result<Option<(DecisionTreeNode, u32)>, SearchError> {
+    if node.game_state != GameState::InProgress {
+        return Err(SearchError::GameOver(node.game_state));
+    }
+    let config = node.position.board_state.config.clone();
+    if seats.len() < config.players.len() {
+        return Err(SearchError::MissingHeuristics {
+            players: config.players.len(),
+            heuristics: seats.len(),
+        });
+    }
+    if seats.iter().any(|seat| seat.depth == 0) {
+        return Err(SearchError::ZeroDepth);
+    }
+    let mut draws = DrawTracker::new(&node.position);
+    let mut rounds: u32 = 0;
     loop {
-        match log_level {
-            LogLevel::None => {}
-            _ => {
-                println!("Playing round {}", rounds);
-            }
+        if rounds_limit == Some(rounds) {
+            return Ok(None);
         }
-        match rounds_limit {
-            Some(limit) => {
-                if rounds == limit {
-                    return None;
-                }
-            }
-            None => {}
+        let seat = &mut seats[config.seat(node.position.to_move)];
+        let (_, eval_node) = search(node, seat.function, seat.depth, seat.heuristic, rounds);
+        let moved = node.game_state == GameState::InProgress;
+        let finished = play_searched_move(node, eval_node, &mut draws, record);
+        if moved {
+            rounds += 1;
         }
-        match result {
-            Some(some_result) => match some_result {
-                MinMaxResult::Eval(eval, eval_node) => {
-                    *node = node.children.swap_remove(eval_node);
-
-                    if matches!(log_level, LogLevel::All) {
-                        println!("Evaluated node is: {} with score: {}", eval_node, eval);
-                        println!("Children of node: {}", &node.children.len());
-                        match current_heuristic {
-                            false => println!("Evaluated with heur.: {}", heuristics.0.name()),
-                            true => println!("Evaluated with heur.: {}", heuristics.1.name()),
-                        }
-                        println!("{}", &node);
-                    }
-
-                    player = player.other();
-                    current_heuristic = !current_heuristic;
-
-                    result = match current_heuristic {
-                        false => Some(alfa_beta_inner(
-                            node,
-                            max_depth,
-                            heuristics.0,
-                            0,
-                            true,
-                            f32::NEG_INFINITY,
-                            f32::INFINITY,
-                            player,
-                            rounds,
-                        )),
-                        true => Some(alfa_beta_inner(
-                            node,
-                            max_depth,
-                            heuristics.1,
-                            0,
-                            true,
-                            f32::NEG_INFINITY,
-                            f32::INFINITY,
-                            player,
-                            rounds,
-                        )),
-                    }
-                }
-                MinMaxResult::Leaf(final_node, from_depth) => {
-                    return Some((final_node, max_depth - from_depth, rounds))
-                }
-            },
-            None => {
-                if matches!(log_level, LogLevel::All) {
-                    println!("First node:\n{}", &node);
-                }
-                result = Some(alfa_beta_inner(
-                    node,
-                    max_depth,
-                    heuristics.0,
-                    0,
-                    true,
-                    f32::NEG_INFINITY,
-                    f32::INFINITY,
-                    player,
-                    rounds,
-                ))
-            }
+        if finished {
+            return Ok(Some((node.clone(), rounds)));
         }
-        rounds += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::halma::{BoardConfig, BoardState, Position};
+    use crate::heuristics::{TableBuilder, WeightedHeuristic};
+
+    use std::rc::Rc;
+
+    fn start(name: &str) -> (DecisionTreeNode, WeightedHeuristic) {
+        let config = Rc::new(BoardConfig::named(name).unwrap());
+        let heuristic = WeightedHeuristic::proximity(&config, &TableBuilder::default(), 1.);
+        let first_player = config.players[0];
+        let position = Position::new(BoardState::start_position(config), first_player);
+        (DecisionTreeNode::new(position), heuristic)
+    }
+
+    #[test]
+    fn bad_searches_are_errors() {
+        let (mut node, mut heuristic) = start("8x8-10");
+        let mut other = heuristic.clone();
+        let mut heuristics: Vec<&mut dyn Heuristic> = vec![&mut heuristic, &mut other];
+        let zero_depth = minimax(
+            &mut node,
+            0,
+            &mut heuristics,
+            None,
+            &LogLevel::None,
+            &mut None,
+        );
+        assert!(matches!(zero_depth, Err(SearchError::ZeroDepth)));
+        let zero_depth = alfa_beta(
+            &mut node,
+            0,
+            &mut heuristics,
+            None,
+            &LogLevel::None,
+            &mut None,
+        );
+        assert!(matches!(zero_depth, Err(SearchError::ZeroDepth)));
+        let missing = alfa_beta(
+            &mut node,
+            1,
+            &mut heuristics[..1],
+            None,
+            &LogLevel::None,
+            &mut None,
+        );
+        assert!(matches!(
+            missing,
+            Err(SearchError::MissingHeuristics {
+                players: 2,
+                heuristics: 1
+            })
+        ));
+        let mut seats: Vec<Seat> = heuristics
+            .into_iter()
+            .enumerate()
+            .map(|(depth, heuristic)| Seat {
+                function: SearchFunction::AlfaBeta,
+                depth: depth as u32,
+                heuristic,
+            })
+            .collect();
+        let zero_depth = play_game(&mut node, &mut seats, None, &mut None);
+        assert!(matches!(zero_depth, Err(SearchError::ZeroDepth)));
+        node.game_state = GameState::Won(node.position.to_move);
+        seats[0].depth = 1;
+        let game_over = play_game(&mut node, &mut seats, None, &mut None);
+        assert!(matches!(game_over, Err(SearchError::GameOver(_))));
+    }
+
+    // the game ends with the move that ends it, and every round counted was played
+    #[test]
+    fn games_end_on_played_moves() {
+        let (mut node, mut heuristic) = start("8x8-10");
+        let mut other = heuristic.clone();
+        let mut heuristics: Vec<&mut dyn Heuristic> = vec![&mut heuristic, &mut other];
+        let mut record = GameRecord::new(node.position.clone());
+        let limited = alfa_beta(
+            &mut node.clone(),
+            1,
+            &mut heuristics,
+            Some(3),
+            &LogLevel::None,
+            &mut None,
+        );
+        assert!(limited.unwrap().is_none());
+        let (end, rounds) = alfa_beta(
+            &mut node,
+            1,
+            &mut heuristics,
+            None,
+            &LogLevel::None,
+            &mut Some(&mut record),
+        )
+        .unwrap()
+        .unwrap();
+        assert_ne!(end.game_state, GameState::InProgress);
+        assert_eq!(record.result, Some(end.game_state));
+        assert_eq!(record.moves.len() as u32, rounds);
+        assert_eq!(end.position.ply, rounds);
     }
 }
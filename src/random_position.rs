@@ -0,0 +1,93 @@
+use crate::decision_tree::DecisionTreeNode;
+use crate::halma::{BoardState, Coords, GameState, Player, Position};
+
+use rand::prelude::*;
+
+// plays random legal moves from `position` for `plies` moves, or until the next move would end
+// the game, so the result is always a position of a game still in progress
+pub fn random_playout(position: &Position, plies: u32, rng: &mut impl Rng) -> Position {
+    let mut node = DecisionTreeNode::new(position.clone());
+    for _ in 0..plies {
+        node.generate_children();
+        let mut children: Vec<DecisionTreeNode> = node
+            .children
+            .drain(..)
+            .filter(|child| child.game_state == GameState::InProgress)
+            .collect();
+        if children.is_empty() {
+            break;
+        }
+        let chosen = rng.gen_range(0..children.len());
+        node = children.swap_remove(chosen);
+    }
+    node.position
+}
+
+// puts `pieces` pieces of every player in the game on random empty squares, at least one each,
+// with the player to move picked at random too. Pieces never land in their own target, so nobody
+// has won yet, and they are placed in turns so that a crowded board still gives every player some
+pub fn random_placement(board_state: &BoardState, pieces: usize, rng: &mut impl Rng) -> Position {
+    let config = board_state.config.clone();
+    let mut squares: Vec<Coords> = (0..config.height)
+        .flat_map(|y| (0..config.width).map(move |x| Coords { x, y }))
+        .filter(|coords| config.is_in_board(*coords))
+        .collect();
+    squares.shuffle(rng);
+    let mut placed = BoardState {
+        config: config.clone(),
+        pieces: vec![Vec::new(); Player::ALL.len()],
+    };
+    for _ in 0..pieces.max(1) {
+        for player in &config.players {
+            let target = config.target(*player);
+            if let Some(idx) = squares.iter().position(|coords| !target.contains(coords)) {
+                placed.coords_mut(*player).push(squares.swap_remove(idx));
+            }
+        }
+    }
+    let to_move = *config.players.choose(rng).unwrap();
+    Position::new(placed, to_move)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::halma::{position_from_str, position_to_string, BoardConfig};
+
+    use std::rc::Rc;
+
+    fn assert_round_trip(position: &Position) {
+        let text = position_to_string(position);
+        let parsed = position_from_str(&text).unwrap_or_else(|error| panic!("{}\n{}", error, text));
+        assert_eq!(
+            parsed.board_state.sorted_pieces(),
+            position.board_state.sorted_pieces(),
+            "{}",
+            text
+        );
+        assert_eq!(parsed.to_move, position.to_move, "{}", text);
+    }
+
+    // the generated corpus is meant as parser input, so every board has to read back as written
+    #[test]
+    fn generated_boards_parse_back() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for name in BoardConfig::VARIANTS {
+            let config = Rc::new(BoardConfig::named(name).unwrap());
+            let start = Position::new(
+                BoardState::start_position(config.clone()),
+                config.players[0],
+            );
+            for pieces in [0, 1, 3, 10, 1000] {
+                let position = random_placement(&start.board_state, pieces, &mut rng);
+                for player in &config.players {
+                    assert!(!position.board_state.coords(*player).is_empty());
+                }
+                assert_round_trip(&position);
+            }
+            for plies in [1, 7, 30] {
+                assert_round_trip(&random_playout(&start, plies, &mut rng));
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use crate::halma::{BoardConfig, BoardState, Coords, Move, Player, Topology};
+
+use std::io::IsTerminal;
+
+const RESET: &str = "\x1b[0m";
+const BLACK_PIECE: &str = "\x1b[1;34m";
+const WHITE_PIECE: &str = "\x1b[1;97m";
+const RED_PIECE: &str = "\x1b[1;31m";
+const GREEN_PIECE: &str = "\x1b[1;32m";
+const BLUE_PIECE: &str = "\x1b[1;36m";
+const YELLOW_PIECE: &str = "\x1b[1;33m";
+const LABEL: &str = "\x1b[2m";
+
+const BLACK_CAMP_BG: &str = "\x1b[48;5;17m";
+const WHITE_CAMP_BG: &str = "\x1b[48;5;238m";
+const RED_CAMP_BG: &str = "\x1b[48;5;52m";
+const GREEN_CAMP_BG: &str = "\x1b[48;5;22m";
+const BLUE_CAMP_BG: &str = "\x1b[48;5;23m";
+const YELLOW_CAMP_BG: &str = "\x1b[48;5;58m";
+const EMPTY_BG: &str = "\x1b[48;5;235m";
+const MOVE_FROM_BG: &str = "\x1b[48;5;94m";
+const MOVE_PATH_BG: &str = "\x1b[48;5;30m";
+const MOVE_TO_BG: &str = "\x1b[48;5;28m";
+
+fn piece_color(player: Player) -> &'static str {
+    match player {
+        Player::Black => BLACK_PIECE,
+        Player::White => WHITE_PIECE,
+        Player::Red => RED_PIECE,
+        Player::Green => GREEN_PIECE,
+        Player::Blue => BLUE_PIECE,
+        Player::Yellow => YELLOW_PIECE,
+    }
+}
+
+fn camp_background(player: Player) -> &'static str {
+    match player {
+        Player::Black => BLACK_CAMP_BG,
+        Player::White => WHITE_CAMP_BG,
+        Player::Red => RED_CAMP_BG,
+        Player::Green => GREEN_CAMP_BG,
+        Player::Blue => BLUE_CAMP_BG,
+        Player::Yellow => YELLOW_CAMP_BG,
+    }
+}
+
+// colors are only used when printing straight to a terminal,
+// redirected output (and NO_COLOR) gets the plain version
+pub fn use_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+pub fn render_board(board_state: &BoardState, last_move: Option<&Move>, colored: bool) -> String {
+    if colored {
+        render_colored(board_state, last_move)
+    } else {
+        render_plain(board_state, last_move)
+    }
+}
+
+// square boards get letters above every column, the star board has no straight columns to label
+fn column_labels(config: &BoardConfig) -> String {
+    let mut labels = "   ".to_owned();
+    if config.topology == Topology::Square {
+        for x in 0..config.width as u8 {
+            labels += &format!(" {} ", (b'a' + x) as char);
+        }
+    }
+    labels
+}
+
+// a square is three characters wide, on the star every text column is a single character
+// so that the half cell shift of the rows lines up
+fn pad_cell(config: &BoardConfig, cell: &str) -> String {
+    match config.topology {
+        Topology::Square => format!(" {} ", cell),
+        Topology::Star { .. } => cell.to_owned(),
+    }
+}
+
+fn render_plain(board_state: &BoardState, last_move: Option<&Move>) -> String {
+    let config = &board_state.config;
+    let mut base_string = column_labels(config) + "\n";
+    for y in 0..config.height {
+        base_string += &format!("{:>2} ", y + 1);
+        for column in 0..config.text_width() {
+            let tile = match config.text_cell(column, y) {
+                Some(coords) => match board_state.player_at(coords) {
+                    Some(player) => player.digit(),
+                    None => '.',
+                },
+                None => ' ',
+            };
+            base_string += &pad_cell(config, &tile.to_string());
+        }
+        base_string += &format!(" {}\n", y + 1);
+    }
+    base_string += &column_labels(config);
+    base_string += "\n";
+    if let Some(last_move) = last_move {
+        base_string += &format!("Last move: {}\n", last_move);
+    }
+    base_string
+}
+
+fn render_colored(board_state: &BoardState, last_move: Option<&Move>) -> String {
+    let config = &board_state.config;
+    let mut base_string = format!("{}{}{}\n", LABEL, column_labels(config), RESET);
+    for y in 0..config.height {
+        base_string += &format!("{}{:>2}{} ", LABEL, y + 1, RESET);
+        for column in 0..config.text_width() {
+            let coords: Coords = match config.text_cell(column, y) {
+                Some(coords) => coords,
+                None => {
+                    base_string += &pad_cell(config, " ");
+                    continue;
+                }
+            };
+            let background = match last_move {
+                Some(last_move) if last_move.to == coords => MOVE_TO_BG,
+                Some(last_move) if last_move.from == coords => MOVE_FROM_BG,
+                Some(last_move) if last_move.path.contains(&coords) => MOVE_PATH_BG,
+                _ => match config
+                    .players
+                    .iter()
+                    .find(|player| config.base(**player).contains(&coords))
+                {
+                    Some(player) => camp_background(*player),
+                    None => EMPTY_BG,
+                },
+            };
+            let piece = match board_state.player_at(coords) {
+                Some(player) => format!("{}●", piece_color(player)),
+                None => format!("{}·", LABEL),
+            };
+            base_string += &format!("{}{}{}", background, pad_cell(config, &piece), RESET);
+        }
+        base_string += &format!(" {}{}{}\n", LABEL, y + 1, RESET);
+    }
+    base_string += &format!("{}{}{}\n", LABEL, column_labels(config), RESET);
+    let legend: Vec<String> = config
+        .players
+        .iter()
+        .map(|player| format!("{}●{} {:?}", piece_color(*player), RESET, player))
+        .collect();
+    base_string += &legend.join("  ");
+    if let Some(last_move) = last_move {
+        base_string += &format!("  last move: {}", last_move);
+    }
+    base_string += "\n";
+    base_string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    fn start() -> BoardState {
+        BoardState::start_position(Rc::new(BoardConfig::named("8x8-10").unwrap()))
+    }
+
+    #[test]
+    fn plain_boards_have_labels_and_the_last_move() {
+        let board_state = start();
+        let plain = render_board(&board_state, None, false);
+        let lines: Vec<&str> = plain.lines().collect();
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0].trim(), "a  b  c  d  e  f  g  h");
+        assert_eq!(lines[1], " 1  1  1  1  1  .  .  .  .  1");
+        assert_eq!(lines[8], " 8  .  .  .  .  2  2  2  2  8");
+        assert!(!plain.contains('\x1b'));
+
+        let step: Move = "d1-e2".parse().unwrap();
+        let plain = render_board(&board_state, Some(&step), false);
+        assert!(plain.ends_with("Last move: d1-e2\n"));
+    }
+
+    #[test]
+    fn colored_boards_highlight_the_last_move() {
+        let board_state = start();
+        let colored = render_board(&board_state, None, true);
+        assert!(!colored.contains(MOVE_TO_BG));
+        assert_eq!(colored.matches('●').count(), 20 + 2);
+
+        let jump: Move = "c1-e3-e5".parse().unwrap();
+        let colored = render_board(&board_state, Some(&jump), true);
+        assert_eq!(colored.matches(MOVE_FROM_BG).count(), 1);
+        assert_eq!(colored.matches(MOVE_PATH_BG).count(), 1);
+        assert_eq!(colored.matches(MOVE_TO_BG).count(), 1);
+        assert!(colored.contains("last move: c1-e3-e5"));
+    }
+}
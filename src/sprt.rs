@@ -0,0 +1,168 @@
+use crate::halma::Position;
+use crate::tournament::{Agent, MatchSettings, PairResult};
+
+use rand::Rng;
+
+use std::{error::Error, fmt::Display};
+
+// sequential probability ratio test of `elo0` against `elo1`, the Elo advantage of one agent
+// over another; `alpha` and `beta` are the chances of accepting the wrong hypothesis
+#[derive(Clone, Debug)]
+pub struct Sprt {
+    pub elo0: f32,
+    pub elo1: f32,
+    pub alpha: f32,
+    pub beta: f32,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Sprt {
+            elo0: 0.,
+            elo1: 50.,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SprtResult {
+    // the first agent is at least `elo1` stronger
+    AcceptH1,
+    // the first agent is at most `elo0` stronger
+    AcceptH0,
+    // the game limit ran out first
+    Inconclusive,
+}
+
+impl Display for SprtResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SprtResult::AcceptH1 => write!(f, "H1 accepted"),
+            SprtResult::AcceptH0 => write!(f, "H0 accepted"),
+            SprtResult::Inconclusive => write!(f, "inconclusive"),
+        }
+    }
+}
+
+// the expected score of a player `elo` points stronger
+fn expected_score(elo: f32) -> f32 {
+    1. / (1. + 10f32.powf(-elo / 400.))
+}
+
+impl Sprt {
+    // the log-likelihood ratio is below the first bound when H0 is accepted
+    // and above the second one when H1 is
+    pub fn bounds(&self) -> (f32, f32) {
+        (
+            (self.beta / (1. - self.alpha)).ln(),
+            ((1. - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    // the normal approximation of the log-likelihood ratio of the results so far
+    pub fn llr(&self, result: &PairResult) -> f32 {
+        let games = result.games() as f32;
+        if games == 0. {
+            return 0.;
+        }
+        let score = result.score() / games;
+        let variance = result.variance();
+        let (score0, score1) = (expected_score(self.elo0), expected_score(self.elo1));
+        games * (score1 - score0) * (2. * score - score0 - score1) / (2. * variance)
+    }
+
+    pub fn decide(&self, result: &PairResult) -> Option<SprtResult> {
+        let llr = self.llr(result);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            Some(SprtResult::AcceptH1)
+        } else if llr <= lower {
+            Some(SprtResult::AcceptH0)
+        } else {
+            None
+        }
+    }
+
+    // plays pairs of games of `first` against `second` until one of the hypotheses
+    // is accepted or `max_games` were played, printing the progress after every pair
+    pub fn run(
+        &self,
+        position: &Position,
+        first: &Agent,
+        second: &Agent,
+        max_games: u32,
+        settings: &MatchSettings,
+        rng: &mut impl Rng,
+    ) -> Result<(SprtResult, PairResult), Box<dyn Error>> {
+        let mut result = PairResult::default();
+        let (lower, upper) = self.bounds();
+        while result.games() < max_games {
+            for game in settings.play_pair(position, first, second, rng)? {
+                result.add(&game);
+            }
+            let (elo, low, high) = result.elo();
+            println!(
+                "Games {}: {}, Elo {:+.0} ({:+.0}, {:+.0}), LLR {:.2} ({:.2}, {:.2})",
+                result.games(),
+                result,
+                elo,
+                low,
+                high,
+                self.llr(&result),
+                lower,
+                upper
+            );
+            if let Some(decision) = self.decide(&result) {
+                return Ok((decision, result));
+            }
+        }
+        Ok((SprtResult::Inconclusive, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(wins: u32, losses: u32, draws: u32) -> PairResult {
+        PairResult {
+            wins,
+            losses,
+            draws,
+            ..PairResult::default()
+        }
+    }
+
+    #[test]
+    fn bounds_and_expected_scores() {
+        let (lower, upper) = Sprt::default().bounds();
+        assert!((lower + 19f32.ln()).abs() < 1e-5);
+        assert!((upper - 19f32.ln()).abs() < 1e-5);
+        assert_eq!(expected_score(0.), 0.5);
+        assert!((expected_score(400.) - 10. / 11.).abs() < 1e-6);
+        assert!((expected_score(-100.) + expected_score(100.) - 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn llr_decides_for_the_closer_hypothesis() {
+        let sprt = Sprt::default();
+        assert_eq!(sprt.llr(&result(0, 0, 0)), 0.);
+        assert_eq!(sprt.decide(&result(0, 0, 0)), None);
+        // even results are below the midpoint of the hypotheses
+        assert!(sprt.llr(&result(0, 0, 10)) < 0.);
+        assert_eq!(
+            sprt.decide(&result(150, 100, 0)),
+            Some(SprtResult::AcceptH1)
+        );
+        assert_eq!(
+            sprt.decide(&result(100, 100, 50)),
+            Some(SprtResult::AcceptH0)
+        );
+        assert_eq!(sprt.decide(&result(6, 4, 0)), None);
+        // a sweep is strong evidence, but not certainty
+        let sweep = sprt.llr(&result(5, 0, 0));
+        assert!(sweep.is_finite() && sweep > 0.);
+    }
+}
@@ -0,0 +1,340 @@
+use crate::halma::{BoardConfig, BoardState, Coords, GameRecord, Move, Player, Topology};
+
+use std::{error::Error, fs, path::Path};
+
+const SQUARE: i32 = 32;
+const MARGIN: i32 = 24;
+
+const EMPTY_FILL: &str = "#f5f5f0";
+const PATH_STROKE: &str = "#d9480f";
+
+pub struct SvgOptions<'a> {
+    pub show_path: bool,
+    // optional value drawn in every square, e.g. a heuristic's proximity table
+    pub square_values: Option<&'a dyn Fn(Coords) -> f32>,
+    // time each frame of an animated replay is shown for
+    pub frame_seconds: f32,
+}
+
+impl Default for SvgOptions<'_> {
+    fn default() -> Self {
+        SvgOptions {
+            show_path: true,
+            square_values: None,
+            frame_seconds: 0.5,
+        }
+    }
+}
+
+fn camp_fill(player: Player) -> &'static str {
+    match player {
+        Player::Black => "#c9d3e6",
+        Player::White => "#e6d9c9",
+        Player::Red => "#efc9c9",
+        Player::Green => "#cde6c9",
+        Player::Blue => "#c9e3e6",
+        Player::Yellow => "#ece6c2",
+    }
+}
+
+fn piece_fill(player: Player) -> &'static str {
+    match player {
+        Player::Black => "#1d2433",
+        Player::White => "#fdfdfd",
+        Player::Red => "#c0392b",
+        Player::Green => "#27ae60",
+        Player::Blue => "#2980b9",
+        Player::Yellow => "#f1c40f",
+    }
+}
+
+// star cells sit half a cell apart horizontally and sqrt(3)/2 of a cell apart vertically
+const STAR_ROW: f32 = SQUARE as f32 * 0.866;
+
+fn square_center(config: &BoardConfig, coords: Coords) -> (i32, i32) {
+    match config.topology {
+        Topology::Square => (
+            MARGIN + coords.x as i32 * SQUARE + SQUARE / 2,
+            MARGIN + coords.y as i32 * SQUARE + SQUARE / 2,
+        ),
+        Topology::Star { .. } => (
+            MARGIN + config.text_column(coords) as i32 * SQUARE / 2 + SQUARE / 2,
+            MARGIN + (coords.y as f32 * STAR_ROW) as i32 + SQUARE / 2,
+        ),
+    }
+}
+
+fn square_origin(config: &BoardConfig, coords: Coords) -> (i32, i32) {
+    let (x, y) = square_center(config, coords);
+    (x - SQUARE / 2, y - SQUARE / 2)
+}
+
+fn svg_size(config: &BoardConfig) -> (i32, i32) {
+    match config.topology {
+        Topology::Square => (
+            config.width as i32 * SQUARE + 2 * MARGIN,
+            config.height as i32 * SQUARE + 2 * MARGIN,
+        ),
+        Topology::Star { .. } => (
+            (config.text_width() as i32 - 1) * SQUARE / 2 + SQUARE + 2 * MARGIN,
+            ((config.height - 1) as f32 * STAR_ROW) as i32 + SQUARE + 2 * MARGIN,
+        ),
+    }
+}
+
+fn svg_header(config: &BoardConfig) -> String {
+    let (width, height) = svg_size(config);
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\">\n",
+        width, height
+    )
+}
+
+// squares, camps, coordinate labels and the optional per-square values
+fn svg_background(config: &BoardConfig, options: &SvgOptions) -> String {
+    let mut svg = String::new();
+    for y in 0..config.height {
+        for x in 0..config.width {
+            let coords = Coords { x, y };
+            if !config.is_in_board(coords) {
+                continue;
+            }
+            let (left, top) = square_origin(config, coords);
+            let fill = match config
+                .players
+                .iter()
+                .find(|player| config.base(**player).contains(&coords))
+            {
+                Some(player) => camp_fill(*player),
+                None => EMPTY_FILL,
+            };
+            svg += &match config.topology {
+                Topology::Square => format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#999\" stroke-width=\"0.5\"/>\n",
+                    left, top, SQUARE, SQUARE, fill
+                ),
+                Topology::Star { .. } => format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"#999\" stroke-width=\"0.5\"/>\n",
+                    left + SQUARE / 2, top + SQUARE / 2, SQUARE / 2 - 1, fill
+                ),
+            };
+            if let Some(square_values) = options.square_values {
+                svg += &format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"8\" fill=\"#777\">{:.1}</text>\n",
+                    left + 2,
+                    top + 9,
+                    square_values(coords)
+                );
+            }
+        }
+    }
+    for x in 0..config.width as i32 {
+        if config.topology != Topology::Square {
+            break;
+        }
+        svg += &format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\">{}</text>\n",
+            MARGIN + x * SQUARE + SQUARE / 2,
+            MARGIN - 8,
+            (b'a' + x as u8) as char
+        );
+    }
+    for y in 0..config.height {
+        let (_, center) = square_center(config, Coords { x: 0, y });
+        svg += &format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\">{}</text>\n",
+            MARGIN / 2,
+            center + 4,
+            y + 1
+        );
+    }
+    svg
+}
+
+// pieces and the last move, without the board underneath
+fn svg_pieces(board_state: &BoardState, last_move: Option<&Move>, options: &SvgOptions) -> String {
+    let config = &board_state.config;
+    let mut svg = String::new();
+    if let Some(last_move) = last_move {
+        let (left, top) = square_origin(config, last_move.from);
+        svg += &format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\" stroke-dasharray=\"4 2\"/>\n",
+            left + 1, top + 1, SQUARE - 2, SQUARE - 2, PATH_STROKE
+        );
+        if options.show_path {
+            let mut points = format!(
+                "{},{}",
+                square_center(config, last_move.from).0,
+                square_center(config, last_move.from).1
+            );
+            for point in &last_move.path {
+                let (x, y) = square_center(config, *point);
+                points += &format!(" {},{}", x, y);
+            }
+            svg += &format!(
+                "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"3\" stroke-linejoin=\"round\" opacity=\"0.8\"/>\n",
+                points, PATH_STROKE
+            );
+        }
+    }
+    for (coords, fill) in board_state.config.players.iter().flat_map(|player| {
+        board_state
+            .coords(*player)
+            .iter()
+            .map(|coords| (coords, piece_fill(*player)))
+    }) {
+        let (x, y) = square_center(config, *coords);
+        let stroke_width = match last_move {
+            Some(last_move) if last_move.to == *coords => 3,
+            _ => 1,
+        };
+        svg += &format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"#333\" stroke-width=\"{}\"/>\n",
+            x,
+            y,
+            SQUARE * 3 / 8,
+            fill,
+            stroke_width
+        );
+    }
+    svg
+}
+
+pub fn board_to_svg(
+    board_state: &BoardState,
+    last_move: Option<&Move>,
+    options: &SvgOptions,
+) -> String {
+    svg_header(&board_state.config)
+        + &svg_background(&board_state.config, options)
+        + &svg_pieces(board_state, last_move, options)
+        + "</svg>\n"
+}
+
+// a single SVG which steps through every position of the game in a loop
+pub fn game_to_animated_svg(record: &GameRecord, options: &SvgOptions) -> String {
+    let positions = record.positions();
+    let frames = positions.len();
+    let duration = frames as f32 * options.frame_seconds;
+    let config = &record.start.board_state.config;
+    let (width, height) = svg_size(config);
+    let mut svg = svg_header(config) + &svg_background(config, options);
+    for (frame, (board_state, last_move)) in positions.iter().enumerate() {
+        let start = frame as f32 / frames as f32;
+        let end = (frame + 1) as f32 / frames as f32;
+        let (values, key_times) = if frame == 0 {
+            ("visible;hidden".to_owned(), format!("0;{:.6}", end))
+        } else {
+            (
+                "hidden;visible;hidden".to_owned(),
+                format!("0;{:.6};{:.6}", start, end.min(1.)),
+            )
+        };
+        svg += &format!(
+            "<g visibility=\"hidden\">\n<animate attributeName=\"visibility\" values=\"{}\" keyTimes=\"{}\" dur=\"{}s\" calcMode=\"discrete\" repeatCount=\"indefinite\"/>\n",
+            values, key_times, duration
+        );
+        svg += &svg_pieces(board_state, *last_move, options);
+        svg += &format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"end\">move {}</text>\n</g>\n",
+            width - 4,
+            height - 6,
+            frame
+        );
+    }
+    svg + "</svg>\n"
+}
+
+// writes frame_0000.svg, frame_0001.svg, ... and the animated game.svg into `directory`
+pub fn write_game_svgs(
+    record: &GameRecord,
+    directory: &Path,
+    options: &SvgOptions,
+) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(directory)?;
+    for (frame, (board_state, last_move)) in record.positions().iter().enumerate() {
+        fs::write(
+            directory.join(format!("frame_{:04}.svg", frame)),
+            board_to_svg(board_state, *last_move, options),
+        )?;
+    }
+    fs::write(
+        directory.join("game.svg"),
+        game_to_animated_svg(record, options),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decision_tree::DecisionTreeNode;
+    use crate::halma::Position;
+    use std::rc::Rc;
+
+    fn start(name: &str) -> Position {
+        let config = Rc::new(BoardConfig::named(name).unwrap());
+        let first_player = config.players[0];
+        Position::new(BoardState::start_position(config), first_player)
+    }
+
+    fn pieces(svg: &str) -> usize {
+        svg.matches("stroke=\"#333\"").count()
+    }
+
+    #[test]
+    fn boards_have_every_square_and_piece() {
+        let position = start("8x8-10");
+        let svg = board_to_svg(&position.board_state, None, &SvgOptions::default());
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect").count(), 64);
+        assert_eq!(pieces(&svg), 20);
+
+        let jump: Move = "c1-e3".parse().unwrap();
+        let svg = board_to_svg(&position.board_state, Some(&jump), &SvgOptions::default());
+        assert_eq!(svg.matches("<polyline").count(), 1);
+        let options = SvgOptions {
+            show_path: false,
+            ..SvgOptions::default()
+        };
+        let svg = board_to_svg(&position.board_state, Some(&jump), &options);
+        assert!(!svg.contains("<polyline"));
+
+        let position = start("star-3p");
+        let svg = board_to_svg(&position.board_state, None, &SvgOptions::default());
+        assert_eq!(pieces(&svg), 30);
+    }
+
+    #[test]
+    fn replays_have_a_frame_per_position() {
+        let mut node = DecisionTreeNode::new(start("8x8-10"));
+        let mut record = GameRecord::new(node.position.clone());
+        for _ in 0..3 {
+            node.generate_children();
+            node = node.children.swap_remove(0);
+            record.moves.push(node.position.last_move.clone().unwrap());
+        }
+        let svg = game_to_animated_svg(&record, &SvgOptions::default());
+        assert_eq!(svg.matches("<g visibility").count(), 4);
+        assert_eq!(pieces(&svg), 4 * 20);
+
+        let directory = std::env::temp_dir().join(format!("lista_2_svg_{}", std::process::id()));
+        write_game_svgs(&record, &directory, &SvgOptions::default()).unwrap();
+        let mut files: Vec<String> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(
+            files,
+            [
+                "frame_0000.svg",
+                "frame_0001.svg",
+                "frame_0002.svg",
+                "frame_0003.svg",
+                "game.svg"
+            ]
+        );
+    }
+}
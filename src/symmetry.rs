@@ -0,0 +1,206 @@
+use crate::halma::{BoardConfig, BoardState, Coords, Move, Player, Position};
+
+// reflections and the half turn of the board. The same formulas hold on the star,
+// where they swap or negate its cube coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+    Identity,
+    // x and y swapped, the top left and bottom right corners stay in place
+    MainDiagonal,
+    // the top right and bottom left corners stay in place, black and white swap camps
+    AntiDiagonal,
+    // half turn around the center
+    Rotation,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 4] = [
+        Symmetry::Identity,
+        Symmetry::MainDiagonal,
+        Symmetry::AntiDiagonal,
+        Symmetry::Rotation,
+    ];
+
+    pub fn apply(&self, config: &BoardConfig, coords: Coords) -> Coords {
+        let (right, bottom) = (config.width - 1, config.height - 1);
+        match self {
+            Symmetry::Identity => coords,
+            Symmetry::MainDiagonal => Coords {
+                x: coords.y,
+                y: coords.x,
+            },
+            Symmetry::AntiDiagonal => Coords {
+                x: right - coords.y,
+                y: bottom - coords.x,
+            },
+            Symmetry::Rotation => Coords {
+                x: right - coords.x,
+                y: bottom - coords.y,
+            },
+        }
+    }
+
+    fn apply_move(&self, config: &BoardConfig, player_move: &Move) -> Move {
+        Move {
+            from: self.apply(config, player_move.from),
+            to: self.apply(config, player_move.to),
+            path: player_move
+                .path
+                .iter()
+                .map(|coords| self.apply(config, *coords))
+                .collect(),
+        }
+    }
+
+    // the player whose camp and target every player's ones are mapped onto, indexed
+    // by `Player::index`; `None` when the board, the camps or the turn order are not
+    // symmetric this way
+    pub fn player_map(&self, config: &BoardConfig) -> Option<Vec<Player>> {
+        if matches!(self, Symmetry::MainDiagonal | Symmetry::AntiDiagonal)
+            && config.width != config.height
+        {
+            return None;
+        }
+        let sorted = |squares: &mut Vec<Coords>| squares.sort_by_key(|coords| (coords.y, coords.x));
+        let mut map = Player::ALL.to_vec();
+        for player in &config.players {
+            let mut base: Vec<Coords> = config
+                .base(*player)
+                .iter()
+                .map(|coords| self.apply(config, *coords))
+                .collect();
+            let mut target: Vec<Coords> = config
+                .target(*player)
+                .iter()
+                .map(|coords| self.apply(config, *coords))
+                .collect();
+            sorted(&mut base);
+            sorted(&mut target);
+            let image = config.players.iter().copied().find(|other| {
+                let mut other_base = config.base(*other).to_vec();
+                let mut other_target = config.target(*other).to_vec();
+                sorted(&mut other_base);
+                sorted(&mut other_target);
+                other_base == base && other_target == target
+            })?;
+            map[player.index()] = image;
+        }
+        // mirrored players still have to move in the same order
+        for player in &config.players {
+            let next = config.next_player(*player);
+            if map[next.index()] != config.next_player(map[player.index()]) {
+                return None;
+            }
+        }
+        Some(map)
+    }
+}
+
+// symmetries mapping the board, the camps and the turn order onto themselves
+pub fn symmetries(config: &BoardConfig) -> Vec<Symmetry> {
+    Symmetry::ALL
+        .iter()
+        .copied()
+        .filter(|symmetry| symmetry.player_map(config).is_some())
+        .collect()
+}
+
+// the board mirrored by `symmetry` with the colours swapped to match the mirrored camps
+pub fn mirror_board_state(board_state: &BoardState, symmetry: Symmetry) -> Option<BoardState> {
+    let config = &board_state.config;
+    let map = symmetry.player_map(config)?;
+    let mut pieces = vec![Vec::new(); Player::ALL.len()];
+    for player in Player::ALL {
+        pieces[map[player.index()].index()] = board_state
+            .coords(player)
+            .iter()
+            .map(|coords| symmetry.apply(config, *coords))
+            .collect();
+    }
+    Some(BoardState {
+        config: config.clone(),
+        pieces,
+    })
+}
+
+pub fn mirror_position(position: &Position, symmetry: Symmetry) -> Option<Position> {
+    let config = &position.board_state.config;
+    let map = symmetry.player_map(config)?;
+    Some(Position {
+        board_state: mirror_board_state(&position.board_state, symmetry)?,
+        to_move: map[position.to_move.index()],
+        ply: position.ply,
+        last_move: position
+            .last_move
+            .as_ref()
+            .map(|last_move| symmetry.apply_move(config, last_move)),
+    })
+}
+
+// one representative of all positions symmetric to this one, e.g. as a transposition table
+// or opening book key; it is the one with the smallest player to move and pieces
+pub fn canonical_position(position: &Position) -> Position {
+    symmetries(&position.board_state.config)
+        .into_iter()
+        .filter_map(|symmetry| mirror_position(position, symmetry))
+        .min_by_key(|mirrored| {
+            (
+                mirrored.to_move.index(),
+                mirrored.board_state.sorted_pieces(),
+            )
+        })
+        .unwrap_or_else(|| position.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features;
+    use crate::heuristics::ProximityTables;
+    use crate::random_position::random_playout;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::rc::Rc;
+
+    // every feature but noise and the original top-and-bottom edges values a position the
+    // same as its mirror images, for the player the evaluating one is mapped onto
+    #[test]
+    fn features_are_invariant_under_symmetries() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for name in BoardConfig::VARIANTS {
+            let config = Rc::new(BoardConfig::named(name).unwrap());
+            let tables = ProximityTables::new(&config);
+            let start = Position::new(
+                BoardState::start_position(config.clone()),
+                config.players[0],
+            );
+            for plies in [0, 10, 40] {
+                let position = random_playout(&start, plies, &mut rng);
+                for symmetry in symmetries(&config) {
+                    let map = symmetry.player_map(&config).unwrap();
+                    let mirrored = mirror_board_state(&position.board_state, symmetry).unwrap();
+                    for mut feature in features::all() {
+                        if feature.name() == "noise" || feature.name() == "edges" {
+                            continue;
+                        }
+                        for player in &config.players {
+                            let value = feature.value(&position.board_state, *player, &tables);
+                            let mirrored_value =
+                                feature.value(&mirrored, map[player.index()], &tables);
+                            assert!(
+                                (value - mirrored_value).abs() < 1e-3,
+                                "{} on {} after {} plies, {:?}: {} against {}",
+                                feature.name(),
+                                name,
+                                plies,
+                                symmetry,
+                                value,
+                                mirrored_value
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
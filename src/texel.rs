@@ -0,0 +1,293 @@
+use crate::features::{self, Feature};
+use crate::halma::{game_record_from_str, BoardConfig, GameRecord, GameState, Player};
+use crate::heuristic_config::{to_tidy_toml, FeatureConfig, HeuristicConfig, TableConfig};
+use crate::heuristics::ProximityTables;
+
+use std::{collections::BTreeMap, error::Error, fs, path::Path};
+
+// features weighted next to the tables; the ones built on the tables themselves are covered
+// by the table values and noise has nothing to fit
+pub const TUNED_FEATURES: [&str; 5] = ["home", "target", "edges", "blocking", "mobility"];
+
+// a position of a finished game, every list indexed like the players of the model
+pub struct Sample {
+    // `y * width + x` of every piece of every player
+    squares: Vec<Vec<usize>>,
+    features: Vec<Vec<f32>>,
+    // 1 for a win, 0.5 for a draw and 0 for a loss
+    results: Vec<f32>,
+}
+
+// a proximity table for every player plus a weight for every tuned feature, the evaluation
+// of a player being the sum of the table values under their pieces and the weighted features;
+// the search compares the players by it, so sigmoid(k * (own evaluation - average evaluation
+// of the opponents)) is taken as the expected result
+pub struct TexelModel {
+    pub config: BoardConfig,
+    pub tables: Vec<Vec<f32>>,
+    pub weights: Vec<f32>,
+    pub k: f32,
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1. / (1. + (-x).exp())
+}
+
+// three decimals are plenty for a heuristic file
+fn round(value: f32) -> f32 {
+    (value * 1000.).round() / 1000.
+}
+
+// the records in the given files and in every file of the given directories
+pub fn load_records(paths: &[String]) -> Result<Vec<GameRecord>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if Path::new(path).is_dir() {
+            let mut entries: Vec<_> = fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<_, _>>()?;
+            entries.sort();
+            files.extend(entries.into_iter().filter(|entry| entry.is_file()));
+        } else {
+            files.push(Path::new(path).to_path_buf());
+        }
+    }
+    let mut records = Vec::new();
+    for file in files {
+        let record_str = fs::read_to_string(&file)
+            .map_err(|error| format!("Could not read {}: {}", file.display(), error))?;
+        records.push(
+            game_record_from_str(&record_str)
+                .map_err(|error| format!("Could not parse {}: {}", file.display(), error))?,
+        );
+    }
+    Ok(records)
+}
+
+// every position of every finished game; the games have to be played
+// on the same board
+pub fn samples_from_records(
+    records: &[GameRecord],
+) -> Result<(Vec<Sample>, BoardConfig), Box<dyn Error>> {
+    let first = records.first().ok_or("No games given")?;
+    let config = (*first.start.board_state.config).clone();
+    let mut features: Vec<Box<dyn Feature>> = TUNED_FEATURES
+        .iter()
+        .map(|name| features::named(name).unwrap())
+        .collect();
+    // the feature values do not depend on the tables
+    let tables = ProximityTables::new(&config);
+    let mut samples = Vec::new();
+    for record in records {
+        let record_config = &record.start.board_state.config;
+        if record_config.width != config.width
+            || record_config.height != config.height
+            || record_config.players != config.players
+        {
+            return Err("All games have to be played on the same board".into());
+        }
+        let result = match record.result {
+            Some(result) => result,
+            None => continue,
+        };
+        for (board_state, _) in record.positions() {
+            let players = config.players.iter();
+            samples.push(Sample {
+                squares: players
+                    .clone()
+                    .map(|player| {
+                        board_state
+                            .coords(*player)
+                            .iter()
+                            .map(|coords| {
+                                coords.y as usize * config.width as usize + coords.x as usize
+                            })
+                            .collect()
+                    })
+                    .collect(),
+                features: players
+                    .clone()
+                    .map(|player| {
+                        features
+                            .iter_mut()
+                            .map(|feature| feature.value(&board_state, *player, &tables))
+                            .collect()
+                    })
+                    .collect(),
+                results: players
+                    .map(|player| match result {
+                        GameState::Won(winner) if winner == *player => 1.,
+                        GameState::Won(_) => 0.,
+                        _ => 0.5,
+                    })
+                    .collect(),
+            });
+        }
+    }
+    if samples.is_empty() {
+        return Err("None of the games is finished".into());
+    }
+    Ok((samples, config))
+}
+
+impl TexelModel {
+    // the default tables with the weights of the proximity heuristic
+    pub fn new(config: BoardConfig) -> Self {
+        let proximity = ProximityTables::new(&config);
+        let tables = config
+            .players
+            .iter()
+            .map(|player| proximity.table(*player).concat())
+            .collect();
+        let weights = TUNED_FEATURES
+            .iter()
+            .map(|name| match *name {
+                "edges" | "blocking" => 1.,
+                _ => 0.,
+            })
+            .collect();
+        TexelModel {
+            config,
+            tables,
+            weights,
+            k: 1.,
+        }
+    }
+
+    fn evaluate(&self, sample: &Sample, player: usize) -> f32 {
+        let table = &self.tables[player];
+        let on_table: f32 = sample.squares[player]
+            .iter()
+            .map(|square| table[*square])
+            .sum();
+        let weighted: f32 = sample.features[player]
+            .iter()
+            .zip(&self.weights)
+            .map(|(value, weight)| value * weight)
+            .sum();
+        on_table + weighted
+    }
+
+    // how much better `player` evaluates than the average opponent
+    fn difference(&self, sample: &Sample, player: usize) -> f32 {
+        let opponents = (sample.results.len() - 1) as f32;
+        let others: f32 = (0..sample.results.len())
+            .filter(|other| *other != player)
+            .map(|other| self.evaluate(sample, other))
+            .sum();
+        self.evaluate(sample, player) - others / opponents
+    }
+
+    // mean squared difference between the expected and the actual results
+    pub fn error(&self, samples: &[Sample]) -> f32 {
+        let mut error = 0.;
+        for sample in samples {
+            for (player, result) in sample.results.iter().enumerate() {
+                error += (result - sigmoid(self.k * self.difference(sample, player))).powi(2);
+            }
+        }
+        error / (samples.len() * self.tables.len()) as f32
+    }
+
+    // the scale between evaluations and results fitting the current values best,
+    // searched from 0.001 to 10
+    pub fn fit_k(&mut self, samples: &[Sample]) {
+        let mut best = (self.k, f32::INFINITY);
+        for step in 0..=80 {
+            self.k = 10f32.powf(-3. + step as f32 / 20.);
+            let error = self.error(samples);
+            if error < best.1 {
+                best = (self.k, error);
+            }
+        }
+        self.k = best.0;
+    }
+
+    // Adam steps over the table values and weights, `k` staying fixed
+    pub fn train(&mut self, samples: &[Sample], epochs: u32, learning_rate: f32) {
+        let players = self.tables.len();
+        let table_size = (self.config.width as usize) * (self.config.height as usize);
+        let weights_start = players * table_size;
+        let parameters = weights_start + self.weights.len();
+        let (mut mean, mut variance) = (vec![0.; parameters], vec![0.; parameters]);
+        let (beta1, beta2) = (0.9f32, 0.999f32);
+        for epoch in 1..=epochs {
+            let mut gradient = vec![0.; parameters];
+            for sample in samples {
+                for (player, result) in sample.results.iter().enumerate() {
+                    let expected = sigmoid(self.k * self.difference(sample, player));
+                    let slope = -2. * (result - expected) * expected * (1. - expected) * self.k
+                        / (samples.len() * players) as f32;
+                    // the own evaluation counts fully, the opponents' ones against it
+                    for other in 0..players {
+                        let share = match other == player {
+                            true => slope,
+                            false => -slope / (players - 1) as f32,
+                        };
+                        for square in &sample.squares[other] {
+                            gradient[other * table_size + square] += share;
+                        }
+                        for (idx, value) in sample.features[other].iter().enumerate() {
+                            gradient[weights_start + idx] += share * value;
+                        }
+                    }
+                }
+            }
+            for (idx, slope) in gradient.iter().enumerate() {
+                mean[idx] = beta1 * mean[idx] + (1. - beta1) * slope;
+                variance[idx] = beta2 * variance[idx] + (1. - beta2) * slope * slope;
+                let mean_hat = mean[idx] / (1. - beta1.powi(epoch as i32));
+                let variance_hat = variance[idx] / (1. - beta2.powi(epoch as i32));
+                let change = learning_rate * mean_hat / (variance_hat.sqrt() + 1e-8);
+                match idx.checked_sub(weights_start) {
+                    Some(weight) => self.weights[weight] -= change,
+                    None => self.tables[idx / table_size][idx % table_size] -= change,
+                }
+            }
+            if epoch % 10 == 0 || epoch == epochs {
+                println!("Epoch {}: error {:.6}", epoch, self.error(samples));
+            }
+        }
+    }
+
+    // a heuristic file with the fitted tables and weights
+    pub fn heuristic_config(&self) -> HeuristicConfig {
+        let mut features = vec![FeatureConfig {
+            feature: "progress".to_owned(),
+            weight: 1.,
+        }];
+        for (name, weight) in TUNED_FEATURES.iter().zip(&self.weights) {
+            features.push(FeatureConfig {
+                feature: name.to_string(),
+                weight: round(*weight),
+            });
+        }
+        let values: BTreeMap<String, Vec<Vec<f32>>> = self
+            .config
+            .players
+            .iter()
+            .zip(&self.tables)
+            .map(|(player, table): (&Player, &Vec<f32>)| {
+                let rows = table
+                    .chunks(self.config.width as usize)
+                    .map(|row| row.iter().map(|value| round(*value)).collect())
+                    .collect();
+                (player.name().to_owned(), rows)
+            })
+            .collect();
+        HeuristicConfig {
+            name: Some("Texel".to_owned()),
+            features,
+            table: TableConfig {
+                values,
+                ..TableConfig::default()
+            },
+            ..HeuristicConfig::default()
+        }
+    }
+
+    // the heuristic file as TOML
+    pub fn to_toml(&self) -> Result<String, Box<dyn Error>> {
+        to_tidy_toml(&self.heuristic_config())
+    }
+}
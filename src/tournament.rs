@@ -0,0 +1,396 @@
+use crate::adjudication::Adjudication;
+use crate::decision_tree::DecisionTreeNode;
+use crate::halma::{GameState, Position};
+use crate::heuristic_config::load_heuristic;
+use crate::minimax::{play_game, SearchError, SearchFunction, Seat};
+use crate::random_position::random_playout;
+
+use rand::Rng;
+
+use std::{error::Error, fmt::Display, time::Instant};
+
+// a heuristic as accepted by `load_heuristic` searched with `function` to `depth`,
+// written `<heuristic>[@<minimax|alfabeta>][@<depth>]`, e.g. `complex@alfabeta@3`
+#[derive(Clone, Debug)]
+pub struct Agent {
+    pub name: String,
+    pub heuristic: String,
+    pub function: SearchFunction,
+    pub depth: u32,
+}
+
+impl Agent {
+    // `function` and `depth` are used unless the spec gives its own
+    pub fn parse(spec: &str, function: SearchFunction, depth: u32) -> Result<Self, Box<dyn Error>> {
+        let mut parts = spec.split('@');
+        let mut agent = Agent {
+            name: spec.to_owned(),
+            heuristic: parts.next().unwrap_or_default().to_owned(),
+            function,
+            depth,
+        };
+        for part in parts {
+            match part.parse::<u32>() {
+                Ok(depth) => agent.depth = depth,
+                Err(_) => agent.function = part.parse()?,
+            }
+        }
+        if agent.depth == 0 {
+            return Err(SearchError::ZeroDepth.into());
+        }
+        Ok(agent)
+    }
+}
+
+// the result of a game for the first agent
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+pub struct GameOutcome {
+    pub outcome: Outcome,
+    pub rounds: u32,
+    pub seconds: f32,
+}
+
+impl GameOutcome {
+    // the same game from the point of view of the second agent
+    pub fn mirrored(self) -> Self {
+        GameOutcome {
+            outcome: match self.outcome {
+                Outcome::Win => Outcome::Loss,
+                Outcome::Loss => Outcome::Win,
+                Outcome::Draw => Outcome::Draw,
+            },
+            ..self
+        }
+    }
+}
+
+// plays `first` against `second` from `position`; `first` takes the seat of the first player
+// in the turn order and, with more than two players, every other seat after it
+pub fn play_match_game(
+    position: &Position,
+    first: &Agent,
+    second: &Agent,
+    rounds_limit: Option<u32>,
+    adjudication: &Adjudication,
+) -> Result<GameOutcome, Box<dyn Error>> {
+    let config = &position.board_state.config;
+    let mut heuristics = Vec::new();
+    for seat in 0..config.players.len() {
+        let agent = if seat.is_multiple_of(2) {
+            first
+        } else {
+            second
+        };
+        heuristics.push((agent, load_heuristic(&agent.heuristic, config)?));
+    }
+    let mut seats: Vec<Seat> = heuristics
+        .iter_mut()
+        .map(|(agent, heuristic)| Seat {
+            function: agent.function,
+            depth: agent.depth,
+            heuristic,
+        })
+        .collect();
+    let time = Instant::now();
+    let mut node = DecisionTreeNode::new(position.clone());
+    let (game_state, rounds) = match play_game(&mut node, &mut seats, rounds_limit, &mut None)? {
+        Some((final_node, rounds)) => (final_node.game_state, rounds),
+        None => (
+            adjudication.adjudicate(&node.position.board_state),
+            rounds_limit.unwrap_or_default(),
+        ),
+    };
+    let outcome = match game_state {
+        GameState::Won(player) if config.seat(player).is_multiple_of(2) => Outcome::Win,
+        GameState::Won(_) => Outcome::Loss,
+        _ => Outcome::Draw,
+    };
+    Ok(GameOutcome {
+        outcome,
+        rounds,
+        seconds: time.elapsed().as_secs_f32(),
+    })
+}
+
+// games of one agent against another, from the first one's point of view
+#[derive(Clone, Debug, Default)]
+pub struct PairResult {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub rounds: u32,
+    pub seconds: f32,
+}
+
+impl PairResult {
+    pub fn add(&mut self, game: &GameOutcome) {
+        match game.outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Loss => self.losses += 1,
+            Outcome::Draw => self.draws += 1,
+        }
+        self.rounds += game.rounds;
+        self.seconds += game.seconds;
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    pub fn score(&self) -> f32 {
+        self.wins as f32 + self.draws as f32 / 2.
+    }
+
+    // the spread of the result of a single game around the mean score; a clean sweep has
+    // no spread at all, so it is counted as if one more game had gone the other way, which
+    // keeps the Elo interval and the SPRT from treating a few games as certainty
+    pub fn variance(&self) -> f32 {
+        let games = self.games() as f32;
+        let score = self.score() / games;
+        ((self.wins as f32 * (1. - score).powi(2)
+            + self.losses as f32 * score.powi(2)
+            + self.draws as f32 * (0.5 - score).powi(2))
+            / games)
+            .max(1. / (4. * games))
+    }
+
+    // the Elo difference to the opponents with a 95% confidence interval around it
+    pub fn elo(&self) -> (f32, f32, f32) {
+        let games = self.games() as f32;
+        if games == 0. {
+            return (0., f32::NEG_INFINITY, f32::INFINITY);
+        }
+        let score = self.score() / games;
+        let variance = self.variance();
+        let margin = 1.96 * (variance / games).sqrt();
+        (
+            elo_difference(score),
+            elo_difference(score - margin),
+            elo_difference(score + margin),
+        )
+    }
+}
+
+impl Display for PairResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "+{} -{} ={}", self.wins, self.losses, self.draws)
+    }
+}
+
+// the Elo difference which makes `score` the expected score, infinite for 0 and 1
+pub fn elo_difference(score: f32) -> f32 {
+    if score <= 0. {
+        return f32::NEG_INFINITY;
+    }
+    if score >= 1. {
+        return f32::INFINITY;
+    }
+    // adding zero turns an even score's -0 into 0
+    -400. * (1. / score - 1.).log10() + 0.
+}
+
+// how the games of a match are set up
+pub struct MatchSettings {
+    // random moves played from the start position before the agents take over
+    pub opening_plies: u32,
+    pub rounds_limit: Option<u32>,
+    pub adjudication: Adjudication,
+}
+
+impl MatchSettings {
+    // a random opening played twice by the agents, once from each seat
+    pub fn play_pair(
+        &self,
+        position: &Position,
+        first: &Agent,
+        second: &Agent,
+        rng: &mut impl Rng,
+    ) -> Result<[GameOutcome; 2], Box<dyn Error>> {
+        let opening = random_playout(position, self.opening_plies, rng);
+        let first_game = play_match_game(
+            &opening,
+            first,
+            second,
+            self.rounds_limit,
+            &self.adjudication,
+        )?;
+        let second_game = play_match_game(
+            &opening,
+            second,
+            first,
+            self.rounds_limit,
+            &self.adjudication,
+        )?;
+        Ok([first_game, second_game.mirrored()])
+    }
+}
+
+pub struct Tournament {
+    pub agents: Vec<Agent>,
+    // `results[a][b]` are the games of agent a against agent b
+    pub results: Vec<Vec<PairResult>>,
+}
+
+// every agent plays `pairs` pairs of games against every other one, each pair from its own
+// random opening and with colours swapped
+pub fn run_tournament(
+    position: &Position,
+    agents: Vec<Agent>,
+    pairs: u32,
+    settings: &MatchSettings,
+    rng: &mut impl Rng,
+) -> Result<Tournament, Box<dyn Error>> {
+    let mut results = vec![vec![PairResult::default(); agents.len()]; agents.len()];
+    for first in 0..agents.len() {
+        for second in first + 1..agents.len() {
+            for _ in 0..pairs {
+                let games = settings.play_pair(position, &agents[first], &agents[second], rng)?;
+                for game in games {
+                    println!(
+                        "{} vs {}: {:?} in {} rounds",
+                        agents[first].name, agents[second].name, game.outcome, game.rounds
+                    );
+                    results[first][second].add(&game);
+                    results[second][first].add(&game.mirrored());
+                }
+            }
+        }
+    }
+    Ok(Tournament { agents, results })
+}
+
+impl Tournament {
+    // all games of an agent
+    pub fn total(&self, agent: usize) -> PairResult {
+        let mut total = PairResult::default();
+        for result in &self.results[agent] {
+            total.wins += result.wins;
+            total.losses += result.losses;
+            total.draws += result.draws;
+            total.rounds += result.rounds;
+            total.seconds += result.seconds;
+        }
+        total
+    }
+}
+
+// a crosstable with the results of every pair, the score and Elo of every agent against the
+// whole field and the average length of the games of every pair
+impl Display for Tournament {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name_width = self
+            .agents
+            .iter()
+            .map(|agent| agent.name.len())
+            .max()
+            .unwrap_or_default()
+            .max(5);
+        write!(f, "{:width$}", "", width = name_width + 4)?;
+        for idx in 0..self.agents.len() {
+            write!(f, "{:>12}", idx + 1)?;
+        }
+        writeln!(f, "{:>10}{:>22}", "Score", "Elo (95%)")?;
+        for (idx, agent) in self.agents.iter().enumerate() {
+            write!(
+                f,
+                "{:>2}. {:width$}",
+                idx + 1,
+                agent.name,
+                width = name_width
+            )?;
+            for (opponent, result) in self.results[idx].iter().enumerate() {
+                match opponent == idx {
+                    true => write!(f, "{:>12}", "-")?,
+                    false => write!(f, "{:>12}", result.to_string())?,
+                }
+            }
+            let total = self.total(idx);
+            let (elo, low, high) = total.elo();
+            writeln!(
+                f,
+                "{:>10}{:>22}",
+                format!("{}/{}", total.score(), total.games()),
+                format!("{:+.0} ({:+.0}, {:+.0})", elo, low, high)
+            )?;
+        }
+        writeln!(f)?;
+        for first in 0..self.agents.len() {
+            for second in first + 1..self.agents.len() {
+                let result = &self.results[first][second];
+                let games = result.games().max(1) as f32;
+                writeln!(
+                    f,
+                    "{} vs {}: {}, {:.1} rounds and {:.2} seconds per game",
+                    self.agents[first].name,
+                    self.agents[second].name,
+                    result,
+                    result.rounds as f32 / games,
+                    result.seconds / games
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agent_specs() {
+        let agent = Agent::parse("complex@minimax@3", SearchFunction::AlfaBeta, 2).unwrap();
+        assert_eq!(agent.heuristic, "complex");
+        assert_eq!(agent.function, SearchFunction::Minimax);
+        assert_eq!(agent.depth, 3);
+        let agent = Agent::parse("leading", SearchFunction::AlfaBeta, 2).unwrap();
+        assert_eq!((agent.function, agent.depth), (SearchFunction::AlfaBeta, 2));
+        assert!(Agent::parse("leading@0", SearchFunction::AlfaBeta, 2).is_err());
+        assert!(Agent::parse("leading", SearchFunction::AlfaBeta, 0).is_err());
+        assert!(Agent::parse("leading@deep", SearchFunction::AlfaBeta, 2).is_err());
+    }
+
+    fn result(wins: u32, losses: u32, draws: u32) -> PairResult {
+        PairResult {
+            wins,
+            losses,
+            draws,
+            ..PairResult::default()
+        }
+    }
+
+    #[test]
+    fn elo_estimates() {
+        assert_eq!(elo_difference(0.5), 0.);
+        assert!((elo_difference(0.75) - 190.85).abs() < 0.01);
+        assert!((elo_difference(0.25) + elo_difference(0.75)).abs() < 0.01);
+        assert_eq!(elo_difference(0.), f32::NEG_INFINITY);
+        assert_eq!(elo_difference(1.), f32::INFINITY);
+
+        let even = result(3, 3, 4);
+        assert_eq!(even.score(), 5.);
+        let (elo, low, high) = even.elo();
+        assert_eq!(elo, 0.);
+        assert!(low < 0. && high > 0.);
+        assert!((low + high).abs() < 0.01);
+
+        // a sweep has an infinite estimate but still a finite lower bound
+        let sweep = result(4, 0, 0);
+        assert_eq!(sweep.variance(), 1. / 16.);
+        let (elo, low, _) = sweep.elo();
+        assert_eq!(elo, f32::INFINITY);
+        assert!(low.is_finite() && low > 0.);
+
+        // more games narrow the interval
+        let (_, low, high) = result(30, 30, 40).elo();
+        let (_, wider_low, wider_high) = even.elo();
+        assert!(high - low < wider_high - wider_low);
+        assert_eq!(PairResult::default().elo().0, 0.);
+    }
+}
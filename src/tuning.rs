@@ -0,0 +1,304 @@
+use crate::halma::Position;
+use crate::heuristic_config::{registered, to_tidy_toml};
+use crate::minimax::SearchFunction;
+use crate::tournament::{Agent, MatchSettings, PairResult};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeMap, error::Error, fs, path::PathBuf};
+
+// keeps the first steps from being much longer than the later ones
+const STABILITY: f32 = 5.;
+
+// SPSA: every iteration plays the parameters nudged one way against the same parameters
+// nudged the other way and moves them towards the side which scored better
+pub struct SpsaSettings {
+    pub iterations: u32,
+    // pairs of games played by the two nudged versions every iteration
+    pub pairs: u32,
+    // step size and nudge size, relative to the starting value of every parameter
+    pub learning_rate: f32,
+    pub perturbation: f32,
+    pub seed: u64,
+    pub function: SearchFunction,
+    pub depth: u32,
+    // progress saved after every iteration and picked up again when the file exists
+    pub checkpoint: Option<PathBuf>,
+    // the tuned heuristic, loadable by `load_heuristic`
+    pub out: Option<PathBuf>,
+}
+
+// also the format of the checkpoint file
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpsaState {
+    pub heuristic: String,
+    pub seed: u64,
+    pub iteration: u32,
+    pub params: BTreeMap<String, f32>,
+    // what the parameters are measured against, the starting values
+    pub scales: BTreeMap<String, f32>,
+}
+
+impl SpsaState {
+    pub fn new(heuristic: &str, start: &BTreeMap<String, f32>, seed: u64) -> Self {
+        let scales = start
+            .iter()
+            .map(|(param, value)| {
+                let scale = if value.abs() < 0.1 { 1. } else { value.abs() };
+                (param.clone(), scale)
+            })
+            .collect();
+        SpsaState {
+            heuristic: heuristic.to_owned(),
+            seed,
+            iteration: 0,
+            params: start.clone(),
+            scales,
+        }
+    }
+
+    // `leading:single_power=2.5:multi_power=0.9` for the given parameters
+    pub fn spec(&self, params: &BTreeMap<String, f32>) -> String {
+        let mut spec = self.heuristic.clone();
+        for (param, value) in params {
+            spec += &format!(":{}={}", param, value);
+        }
+        spec
+    }
+
+    // a heuristic file with the current parameters
+    pub fn heuristic_config(&self) -> String {
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|(param, value)| format!("{} = {}", param, value))
+            .collect();
+        format!(
+            "# tuned by SPSA in {} iterations\ntype = \"{}\"\nparams = {{ {} }}\n",
+            self.iteration,
+            self.heuristic,
+            params.join(", ")
+        )
+    }
+}
+
+// the registered parameters of `heuristic` with their defaults, overridden by `overrides`
+pub fn start_params(
+    heuristic: &str,
+    overrides: &BTreeMap<String, f32>,
+) -> Result<BTreeMap<String, f32>, Box<dyn Error>> {
+    let registered = registered(heuristic)?;
+    if registered.params.is_empty() {
+        return Err(format!("{} has no parameters to tune", heuristic).into());
+    }
+    let mut params: BTreeMap<String, f32> = registered
+        .params
+        .iter()
+        .map(|(param, default)| (param.to_string(), *default))
+        .collect();
+    for (param, value) in overrides {
+        if !params.contains_key(param) {
+            return Err(format!("Unknown parameter of {}: {}", heuristic, param).into());
+        }
+        params.insert(param.clone(), *value);
+    }
+    Ok(params)
+}
+
+impl SpsaSettings {
+    fn agent(&self, spec: String) -> Result<Agent, Box<dyn Error>> {
+        Agent::parse(&spec, self.function, self.depth)
+    }
+
+    pub fn run(
+        &self,
+        position: &Position,
+        mut state: SpsaState,
+        match_settings: &MatchSettings,
+    ) -> Result<SpsaState, Box<dyn Error>> {
+        // without games there is no score to follow
+        if self.pairs == 0 {
+            return Err("SPSA needs at least one pair of games per iteration".into());
+        }
+        // the usual SPSA gain sequences, the steps not depending on the number of iterations
+        // left so that a resumed run continues where it stopped
+        while state.iteration < self.iterations {
+            let iteration = state.iteration as f32;
+            let step = self.learning_rate / (iteration + 1. + STABILITY).powf(0.602);
+            let nudge = self.perturbation / (iteration + 1.).powf(0.101);
+            // seeded per iteration, so that a resumed run plays the same games
+            let mut rng = StdRng::seed_from_u64(state.seed.wrapping_add(state.iteration as u64));
+            let directions: BTreeMap<String, f32> = state
+                .params
+                .keys()
+                .map(|param| {
+                    let direction = if rng.gen_bool(0.5) { 1. } else { -1. };
+                    (param.clone(), direction)
+                })
+                .collect();
+            let nudged = |sign: f32| -> BTreeMap<String, f32> {
+                state
+                    .params
+                    .iter()
+                    .map(|(param, value)| {
+                        let change = sign * nudge * directions[param] * state.scales[param];
+                        (param.clone(), value + change)
+                    })
+                    .collect()
+            };
+            let plus = self.agent(state.spec(&nudged(1.)))?;
+            let minus = self.agent(state.spec(&nudged(-1.)))?;
+            let mut result = PairResult::default();
+            for _ in 0..self.pairs {
+                for game in match_settings.play_pair(position, &plus, &minus, &mut rng)? {
+                    result.add(&game);
+                }
+            }
+            // the score of the plus side above one half, between -1 and 1
+            let difference = 2. * result.score() / result.games() as f32 - 1.;
+            for (param, value) in state.params.iter_mut() {
+                let gradient = difference / (2. * nudge * directions[param]);
+                *value += step * gradient * state.scales[param];
+            }
+            state.iteration += 1;
+            println!(
+                "Iteration {}: nudged up {}, now {}",
+                state.iteration,
+                result,
+                state.spec(&state.params)
+            );
+            if let Some(checkpoint) = &self.checkpoint {
+                fs::write(checkpoint, to_tidy_toml(&state)?)?;
+            }
+            if let Some(out) = &self.out {
+                fs::write(out, state.heuristic_config())?;
+            }
+        }
+        Ok(state)
+    }
+
+    // `pairs` pairs of games of the tuned parameters against `baseline`
+    pub fn compare(
+        &self,
+        position: &Position,
+        state: &SpsaState,
+        baseline: &str,
+        pairs: u32,
+        match_settings: &MatchSettings,
+    ) -> Result<PairResult, Box<dyn Error>> {
+        let tuned = self.agent(state.spec(&state.params))?;
+        let baseline = self.agent(baseline.to_owned())?;
+        let mut rng = StdRng::seed_from_u64(state.seed.wrapping_add(state.iteration as u64));
+        let mut result = PairResult::default();
+        for _ in 0..pairs {
+            for game in match_settings.play_pair(position, &tuned, &baseline, &mut rng)? {
+                result.add(&game);
+            }
+        }
+        Ok(result)
+    }
+}
+
+// the checkpoint of an earlier run, if there is one
+pub fn load_checkpoint(settings: &SpsaSettings) -> Result<Option<SpsaState>, Box<dyn Error>> {
+    match &settings.checkpoint {
+        Some(checkpoint) if checkpoint.is_file() => {
+            Ok(Some(toml::from_str(&fs::read_to_string(checkpoint)?)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::halma::{BoardConfig, BoardState};
+    use crate::heuristic_config::HeuristicConfig;
+
+    use std::rc::Rc;
+
+    fn settings(iterations: u32, pairs: u32, checkpoint: Option<PathBuf>) -> SpsaSettings {
+        SpsaSettings {
+            iterations,
+            pairs,
+            learning_rate: 0.1,
+            perturbation: 0.5,
+            seed: 3,
+            function: SearchFunction::AlfaBeta,
+            depth: 1,
+            checkpoint,
+            out: None,
+        }
+    }
+
+    #[test]
+    fn start_params_and_specs() {
+        let overrides = BTreeMap::from([("single_power".to_owned(), 3.)]);
+        let params = start_params("leading", &overrides).unwrap();
+        assert_eq!(
+            params.clone().into_iter().collect::<Vec<_>>(),
+            [
+                ("multi_power".to_owned(), 0.9),
+                ("single_power".to_owned(), 3.)
+            ]
+        );
+        let unknown = BTreeMap::from([("power".to_owned(), 3.)]);
+        assert!(start_params("leading", &unknown).is_err());
+        assert!(start_params("random", &BTreeMap::new()).is_err());
+        assert!(start_params("fastest", &BTreeMap::new()).is_err());
+
+        let state = SpsaState::new("leading", &params, 1);
+        assert_eq!(
+            state.spec(&state.params),
+            "leading:multi_power=0.9:single_power=3"
+        );
+        // the written heuristic builds with the same parameters
+        let written: HeuristicConfig = toml::from_str(&state.heuristic_config()).unwrap();
+        assert_eq!(written.params["single_power"], 3.);
+        assert!(written
+            .build(&BoardConfig::named("8x8-10").unwrap())
+            .is_ok());
+    }
+
+    // a run stopped at its checkpoint and resumed ends up where an uninterrupted run does
+    #[test]
+    fn resumed_runs_continue_where_they_stopped() {
+        let config = Rc::new(BoardConfig::named("8x8-10").unwrap());
+        let position = Position::new(
+            BoardState::start_position(config.clone()),
+            config.players[0],
+        );
+        let match_settings = MatchSettings {
+            opening_plies: 2,
+            rounds_limit: Some(10),
+            adjudication: Default::default(),
+        };
+        let start = start_params("discourage", &BTreeMap::new()).unwrap();
+        let new_state = || SpsaState::new("discourage", &start, 3);
+        assert!(settings(1, 0, None)
+            .run(&position, new_state(), &match_settings)
+            .is_err());
+
+        let whole = settings(3, 1, None)
+            .run(&position, new_state(), &match_settings)
+            .unwrap();
+        let checkpoint =
+            std::env::temp_dir().join(format!("lista_2_spsa_{}.toml", std::process::id()));
+        let first_part = settings(2, 1, Some(checkpoint.clone()));
+        first_part
+            .run(&position, new_state(), &match_settings)
+            .unwrap();
+        let stopped = load_checkpoint(&first_part).unwrap().unwrap();
+        fs::remove_file(&checkpoint).unwrap();
+        assert_eq!(stopped.iteration, 2);
+        let resumed = settings(3, 1, None)
+            .run(&position, stopped, &match_settings)
+            .unwrap();
+        assert_eq!(resumed.iteration, 3);
+        assert_ne!(whole.params, start);
+        for (param, value) in &whole.params {
+            assert!((resumed.params[param] - value).abs() < 1e-4, "{}", param);
+        }
+    }
+}